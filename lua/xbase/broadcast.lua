@@ -1,6 +1,5 @@
 local logger = require "xbase.logger"
 local notify = require "xbase.notify"
-local socket = require "xbase.socket"
 local types = require "xbase.types"
 local msg, tkind, tstatus = types.Message, types.TaskKind, types.TaskStatus
 local config = require("xbase.config").values
@@ -88,86 +87,62 @@ local function task_finish(args)
   end)
 end
 
-function M.start(root, address)
-  local socket = socket:connect(address)
+---Handle a message pushed by the daemon for a registered root
+---@param root string
+---@param item table
+function M.handle(root, item)
+  local type, args = item.type, item.args
 
-  socket._socket:write(string.format("%s\n", vim.loop.os_getpid()), function(err)
-    if err then
-      print(socket._stream_error or err)
-    end
-  end)
+  if msg.task_is_update_current(type) then
+    return task_update(args)
+  end
 
-  socket:read_start(function(chunk)
-    local chunk = vim.trim(chunk)
-    for _, chunk in ipairs(vim.split(chunk, "\n")) do
-      local is_succ, item = pcall(function(ch)
-        return vim.json.decode(ch)
-      end, chunk)
-      if not is_succ then
-        print "PARSE-ERROR===============================================START\n"
-        print(chunk)
-        print "PARSE-ERROR==============================================END\n"
-        return
-      end
+  if msg.task_is_set_current(type) then
+    return task_set(args)
+  end
 
-      local type, args = item.type, item.args
+  if msg.task_is_finish_current(type) then
+    return task_finish(args)
+  end
 
+  if msg.is_notify(type) then
+    notify(args.content, args.level)
+    if string.find(args.content, "Registered") ~= nil then
       vim.schedule(function()
-        if msg.task_is_update_current(type) then
-          return task_update(args)
-        end
-
-        if msg.task_is_set_current(type) then
-          return task_set(args)
-        end
-
-        if msg.task_is_finish_current(type) then
-          return task_finish(args)
-        end
-
-        if msg.is_notify(type) then
-          notify(args.content, args.level)
-          if string.find(args.content, "Registered") ~= nil then
-            vim.schedule(function()
-              vim.defer_fn(function()
-                print "  "
-              end, 2000)
-            end)
-          end
-          return
-        end
-
-        if msg.is_reload_lsp_server(type) then
-          return vim.cmd "LspRestart"
-        end
-
-        if msg.is_open_logger(type) then
-          return logger.toggle(nil, false)
-        end
-
-        if msg.is_log(type) then
-          return logger.log(args.content, args.level)
-        end
-
-        if msg.is_set_state(type) then
-          local key, value = args.key, args.value
-          if key == "runners" then
-            require("xbase.state").runners = value
-          elseif key == "projectInfo" then
-            require("xbase.state").project_info[root] = value
-          end
-          return
-        end
-
-        ---@diagnostic disable-next-line: empty-block
-        if msg.is_set_watching(type) then
-          -- ()
-        end
+        vim.defer_fn(function()
+          print "  "
+        end, 2000)
       end)
     end
-  end)
+    return
+  end
+
+  if msg.is_reload_lsp_server(type) then
+    return vim.cmd "LspRestart"
+  end
+
+  if msg.is_open_logger(type) then
+    return logger.toggle(nil, false)
+  end
+
+  if msg.is_log(type) then
+    return logger.log(args.content, args.level)
+  end
 
-  return socket
+  if msg.is_set_state(type) then
+    local key, value = args.key, args.value
+    if key == "runners" then
+      require("xbase.state").runners = value
+    elseif key == "projectInfo" then
+      require("xbase.state").project_info[root] = value
+    end
+    return
+  end
+
+  ---@diagnostic disable-next-line: empty-block
+  if msg.is_set_watching(type) then
+    -- ()
+  end
 end
 
 return M
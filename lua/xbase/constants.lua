@@ -1,7 +1,21 @@
 local M = {}
 
+---Get environment variable value if set and non-empty
+---@param name string
+---@return string|nil
+local function env(name)
+  local value = vim.env[name]
+  if value ~= nil and value ~= "" then
+    return value
+  end
+end
+
 -- AUTOGENERATED
-M.SOCK_ADDR = '/tmp/xbase.socket'
+M.RUNTIME_DIR = env "XBASE_RUNTIME_DIR"
+  or (env "XDG_RUNTIME_DIR" and env "XDG_RUNTIME_DIR" .. "/xbase")
+  or (string.gsub(env "TMPDIR" or "/tmp", "/$", "") .. "/xbase-" .. vim.loop.getuid())
+M.SOCK_ADDR = env "XBASE_SOCK_ADDR" or M.RUNTIME_DIR .. "/xbase.socket"
+M.PROTOCOL_VERSION = 2
 M.BIN_ROOT = string.gsub('$HOME/.local/share/xbase', '$HOME', vim.env.HOME)
 
 
@@ -5,7 +5,6 @@ local notify = require "xbase.notify"
 local broadcast = require "xbase.broadcast"
 local constants = require "xbase.constants"
 local uv = vim.loop
-local id = vim.loop.os_getpid()
 
 ---@class XBase
 local M = {
@@ -13,8 +12,65 @@ local M = {
   socket = nil,
   ---@type string[] @list of registered roots
   roots = {},
+  ---@type number @id of last sent request
+  last_id = 0,
+  ---@type table<number, function> @response callbacks indexed by request id
+  pending = {},
 }
 
+---Call callback of the request matching a given response
+---@param res table
+local function on_response(res)
+  local callback = M.pending[res.id]
+  M.pending[res.id] = nil
+  if res.error then
+    notify.error(string.format("%s %s", res.error.kind, res.error.msg))
+  elseif callback then
+    callback(res.data)
+  end
+end
+
+---Read new line delimited json objects, either request responses or project messages
+---@param s XBaseSocket
+local function read_responses(s)
+  local buffer = ""
+  s:read_start(function(chunk)
+    buffer = buffer .. chunk
+    local index = string.find(buffer, "\n", 1, true)
+    while index ~= nil do
+      local line = string.sub(buffer, 1, index - 1)
+      buffer = string.sub(buffer, index + 1)
+      index = string.find(buffer, "\n", 1, true)
+
+      if vim.trim(line) ~= "" then
+        local item = vim.json.decode(line)
+        if item.root ~= nil then
+          broadcast.handle(item.root, item.message)
+        else
+          on_response(item)
+        end
+      end
+    end
+  end)
+end
+
+---Connect to daemon socket, start reading responses and introduce the client
+local function connect()
+  M.socket = socket:connect(constants.SOCK_ADDR)
+  read_responses(M.socket)
+
+  M.last_id = M.last_id + 1
+  M.socket:write {
+    id = M.last_id,
+    method = "hello",
+    args = {
+      client_name = "nvim",
+      protocol_version = constants.PROTOCOL_VERSION,
+      capabilities = { "task_kind_resolve", "task_kind_command" },
+    },
+  }
+end
+
 ---Spawn xbase daemon in detached mode and executes cb on first stdout
 ---@param cb function
 function M.spawn_daemon(cb)
@@ -26,7 +82,7 @@ function M.spawn_daemon(cb)
     detached = true,
   })
   stdout:read_start(vim.schedule_wrap(function(_, _)
-    M.socket = socket:connect(constants.SOCK_ADDR)
+    connect()
     stdout:read_stop()
     cb()
   end))
@@ -38,7 +94,7 @@ function M.ensure_connection(cb)
     if uv.fs_stat(constants.SOCK_ADDR) == nil then
       return M.spawn_daemon(cb)
     else
-      M.socket = socket:connect(constants.SOCK_ADDR)
+      connect()
     end
   end
   cb()
@@ -49,20 +105,9 @@ end
 ---@param on_response? function(response:table)
 function M.request(req, on_response)
   M.ensure_connection(function()
-    M.socket:read_start(function(chunk)
-      vim.schedule(function()
-        local res = vim.json.decode(chunk)
-        if res.error then
-          notify.error(string.format("%s %s", res.error.kind, res.error.msg))
-          return
-        else
-          if on_response then
-            on_response(res.data)
-          end
-        end
-      end)
-      M.socket:read_stop()
-    end)
+    M.last_id = M.last_id + 1
+    req.id = M.last_id
+    M.pending[req.id] = on_response
     M.socket:write(req)
   end)
 end
@@ -94,9 +139,9 @@ function M.register(root)
 
   require("xbase.logger").setup()
 
-  local req = { method = "register", args = { id = id, root = root } }
-  M.request(req, function(broadcast_address)
-    broadcast.start(root, broadcast_address)
+  -- The daemon replays recent logs by default, so the logger shows tasks started by other clients
+  local req = { method = "register", args = { root = root } }
+  M.request(req, function()
     M.roots[root] = true
   end)
 end
@@ -19,7 +19,7 @@ function M:write(data)
   if self._stream_error then
     error(self._stream_error)
   end
-  uv.write(self._socket, vim.json.encode(data), function(err)
+  uv.write(self._socket, vim.json.encode(data) .. "\n", function(err)
     if err then
       print(self._stream_error or err)
     end
@@ -15,6 +15,12 @@ M.TaskKind = {
   is_compile = function(kind)
     return kind == "Compile"
   end,
+  is_resolve = function(kind)
+    return kind == "Resolve"
+  end,
+  is_command = function(kind)
+    return kind == "Command"
+  end,
   is_run = function(kind)
     return kind == "Run"
   end,
@@ -23,6 +29,10 @@ M.TaskKind = {
       return "Compiling", "Compiled"
     elseif self.is_generate(kind) then
       return "Generating", "Generated"
+    elseif self.is_resolve(kind) then
+      return "Resolving", "Resolved"
+    elseif self.is_command(kind) then
+      return "Running", "Ran"
     elseif self.is_build(kind) then
       return "Building", "Built"
     elseif self.is_run(kind) then
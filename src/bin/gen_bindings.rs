@@ -30,18 +30,41 @@ fn gen_ts_types_file(path: PathBuf) {
     options.root_namespace = None;
     options.header = None;
 
-    type Requests = (Request, RunRequest, RegisterRequest, DropRequest);
+    type Requests = (
+        Request,
+        RequestMessage,
+        HelloRequest,
+        RunRequest,
+        CommandRequest,
+        RegisterRequest,
+        DropRequest,
+        LogsRequest,
+        ReadLogRequest,
+        BuildHistoryRequest,
+        SimulatorLifecycleRequest,
+        SimulatorRequest,
+        PauseWatchRequest,
+        ResumeWatchRequest,
+    );
     type Responses = (Response, ServerError);
     type Transports = (
         ProjectInfo,
         TargetInfo,
+        WatchBackend,
+        PauseMode,
         Runners,
         Operation,
         BuildSettings,
         DeviceLookup,
         State,
+        SimulatorOptions,
+        DaemonStatus,
+        TaskInfo,
+        TaskLogInfo,
+        BuildHistory,
+        HelloResponse,
     );
-    type Messages = (Message, ContentLevel, TaskKind, TaskStatus);
+    type Messages = (Message, Notification, ContentLevel, TaskKind, TaskStatus);
     type API = (Messages, Transports, Responses, Requests);
 
     write_definition_file::<_, API>(&mut buf, options).unwrap();
@@ -53,17 +76,20 @@ fn gen_ts_types_file(path: PathBuf) {
 
 #[cfg(feature = "gen")]
 fn gen_ts_constant(path: PathBuf) {
-    use xbase::*;
+    use xbase::{fs::*, server::PROTOCOL_VERSION, *};
     let mut output = read_file_content(&path);
 
-    macro_rules! export {
-        ($key:ident) => {
-            output += &format!("export const XBASE_{} = '{}'\n", stringify!($key), &*$key)
-        };
-    }
-
-    export!(SOCK_ADDR);
-
+    output += &format!(
+        "export const XBASE_RUNTIME_DIR =
+  process.env.{RUNTIME_DIR_ENV} ||
+  (process.env.XDG_RUNTIME_DIR
+    ? `${{process.env.XDG_RUNTIME_DIR}}/xbase`
+    : `${{(process.env.TMPDIR || '/tmp').replace(/\\/$/, '')}}/xbase-${{process.getuid!()}}`)\n"
+    );
+    output += &format!(
+        "export const XBASE_SOCK_ADDR = process.env.{SOCK_ADDR_ENV} || `${{XBASE_RUNTIME_DIR}}/{SOCK_NAME}`\n"
+    );
+    output += &format!("export const XBASE_PROTOCOL_VERSION = {PROTOCOL_VERSION}\n");
     output += &format!(
         "export const XBASE_BIN_ROOT = '{BIN_ROOT}'.replace('$HOME', process.env.HOME!)\n"
     );
@@ -73,16 +99,17 @@ fn gen_ts_constant(path: PathBuf) {
 
 #[cfg(feature = "gen")]
 fn gen_lua_constant(path: PathBuf) {
-    use xbase::*;
+    use xbase::{fs::*, server::PROTOCOL_VERSION, *};
     let mut output = read_file_content(&path);
 
-    macro_rules! export {
-        ($key:ident) => {
-            output += &format!("M.{} = '{}'\n", stringify!($key), &*$key)
-        };
-    }
-
-    export!(SOCK_ADDR);
+    output += &format!(
+        "M.RUNTIME_DIR = env \"{RUNTIME_DIR_ENV}\"
+  or (env \"XDG_RUNTIME_DIR\" and env \"XDG_RUNTIME_DIR\" .. \"/xbase\")
+  or (string.gsub(env \"TMPDIR\" or \"/tmp\", \"/$\", \"\") .. \"/xbase-\" .. vim.loop.getuid())\n"
+    );
+    output +=
+        &format!("M.SOCK_ADDR = env \"{SOCK_ADDR_ENV}\" or M.RUNTIME_DIR .. \"/{SOCK_NAME}\"\n");
+    output += &format!("M.PROTOCOL_VERSION = {PROTOCOL_VERSION}\n");
     output += &format!("M.BIN_ROOT = string.gsub('{BIN_ROOT}', '$HOME', vim.env.HOME)\n",);
     output += "\n\nreturn M";
 
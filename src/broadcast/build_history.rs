@@ -0,0 +1,240 @@
+use super::*;
+use crate::util::fs::get_build_cache_dir;
+use crate::{BuildSettings, Device, EventBatch, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use typescript_type_def::TypeDef;
+
+/// Maximum number of build records kept for each project
+const MAX_BUILD_RECORDS: usize = 500;
+
+/// Number of successful builds compared to get build duration trend
+const TREND_WINDOW: usize = 5;
+
+/// Serialize read and write of build history files
+static HISTORY_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(Default::default);
+
+/// Finished build of a project target
+#[derive(Debug, Clone, Serialize, Deserialize, TypeDef)]
+pub struct BuildRecord {
+    pub target: String,
+    pub configuration: String,
+    /// Name of the device the build was made for
+    pub device: Option<String>,
+    /// Start time in seconds since unix epoch
+    pub started_at: u64,
+    /// Duration in milliseconds
+    pub duration: u64,
+    pub success: bool,
+    pub warnings: usize,
+    pub errors: usize,
+    /// First changed file that triggered the build in watch mode
+    pub trigger: Option<PathBuf>,
+}
+
+/// Build statistics of a project target
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct BuildStats {
+    pub target: String,
+    pub builds: usize,
+    pub failures: usize,
+    /// Average duration of successful builds in milliseconds
+    pub average_duration: u64,
+    /// Average warnings count of successful builds
+    pub average_warnings: f64,
+    /// Duration change in percent of the last successful builds compared to the ones before,
+    /// None till there are enough builds to compare
+    pub trend: Option<f64>,
+}
+
+/// Recorded builds of a project along with per target statistics
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct BuildHistory {
+    /// Build records, oldest first
+    pub records: Vec<BuildRecord>,
+    pub stats: Vec<BuildStats>,
+}
+
+/// Get path to build history file of a given project root
+fn build_history_path(root: &Path) -> Result<PathBuf> {
+    Ok(PathBuf::from(get_build_cache_dir(root)?).join("build_history.json"))
+}
+
+/// Read build records of a given project root
+pub async fn build_records(root: &Path) -> Result<Vec<BuildRecord>> {
+    let _lock = HISTORY_LOCK.lock().await;
+    read_build_records(&build_history_path(root)?).await
+}
+
+async fn read_build_records(path: &Path) -> Result<Vec<BuildRecord>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    Ok(serde_json::from_slice(&tokio::fs::read(path).await?)?)
+}
+
+/// Append a build record to build history of a given project root
+async fn push_build_record(root: &Path, record: BuildRecord) -> Result<()> {
+    let _lock = HISTORY_LOCK.lock().await;
+    let path = build_history_path(root)?;
+    let mut records = read_build_records(&path).await.unwrap_or_default();
+
+    records.push(record);
+    if records.len() > MAX_BUILD_RECORDS {
+        records.drain(..records.len() - MAX_BUILD_RECORDS);
+    }
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, serde_json::to_vec(&records)?).await?;
+
+    Ok(())
+}
+
+impl BuildHistory {
+    /// Create build history from records, optionally only for a given target
+    pub fn new(mut records: Vec<BuildRecord>, target: Option<&str>) -> Self {
+        if let Some(target) = target {
+            records.retain(|r| r.target == target);
+        }
+
+        let mut targets = records.iter().map(|r| r.target.clone()).collect::<Vec<_>>();
+        targets.sort();
+        targets.dedup();
+
+        let stats = targets
+            .into_iter()
+            .map(|target| BuildStats::new(target, &records))
+            .collect();
+
+        Self { records, stats }
+    }
+}
+
+impl BuildStats {
+    fn new(target: String, records: &[BuildRecord]) -> Self {
+        let records = records
+            .iter()
+            .filter(|r| r.target == target)
+            .collect::<Vec<_>>();
+        let succeeded = records.iter().filter(|r| r.success).collect::<Vec<_>>();
+
+        let average = |records: &[&&BuildRecord]| {
+            records.iter().map(|r| r.duration).sum::<u64>() / records.len().max(1) as u64
+        };
+
+        let trend = (succeeded.len() >= TREND_WINDOW * 2).then(|| {
+            let (before, recent) = succeeded.split_at(succeeded.len() - TREND_WINDOW);
+            let before = average(&before[before.len() - TREND_WINDOW..]) as f64;
+            let recent = average(recent) as f64;
+            (recent - before) / before.max(1.0) * 100.0
+        });
+
+        Self {
+            builds: records.len(),
+            failures: records.len() - succeeded.len(),
+            average_duration: average(&succeeded),
+            average_warnings: succeeded.iter().map(|r| r.warnings).sum::<usize>() as f64
+                / succeeded.len().max(1) as f64,
+            trend,
+            target,
+        }
+    }
+}
+
+/// Track a build task to be recorded in project build history once finished
+#[derive(Debug)]
+pub struct BuildTracker {
+    target: String,
+    configuration: String,
+    device: Option<String>,
+    trigger: Option<PathBuf>,
+    started_at: SystemTime,
+    instant: Instant,
+    warnings: AtomicUsize,
+    errors: AtomicUsize,
+}
+
+impl BuildTracker {
+    pub fn new(cfg: &BuildSettings, device: Option<&Device>, event: &EventBatch) -> Self {
+        Self {
+            target: cfg.target.clone(),
+            configuration: cfg.configuration.clone(),
+            device: device.map(|d| d.name.clone()),
+            trigger: event.changed_path().cloned(),
+            started_at: SystemTime::now(),
+            instant: Instant::now(),
+            warnings: Default::default(),
+            errors: Default::default(),
+        }
+    }
+
+    /// Count a task output line with a given level
+    pub(crate) fn count(&self, level: &ContentLevel) {
+        let counter = match level {
+            ContentLevel::Warn => &self.warnings,
+            ContentLevel::Error => &self.errors,
+            _ => return,
+        };
+        counter.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Record build in build history of a given project root, written in background
+    pub(crate) fn finish(&self, root: &Path, success: bool) {
+        let record = BuildRecord {
+            target: self.target.clone(),
+            configuration: self.configuration.clone(),
+            device: self.device.clone(),
+            started_at: self
+                .started_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            duration: self.instant.elapsed().as_millis() as u64,
+            success,
+            warnings: self.warnings.load(Ordering::SeqCst),
+            errors: self.errors.load(Ordering::SeqCst),
+            trigger: self.trigger.clone(),
+        };
+
+        let root = root.to_path_buf();
+        tokio::spawn(async move {
+            let target = record.target.clone();
+            if let Err(err) = push_build_record(&root, record).await {
+                tracing::warn!("Failed to record {target} build: {err}");
+            }
+        });
+    }
+}
+
+#[test]
+fn test_build_stats_trend() {
+    let record = |duration, success| BuildRecord {
+        target: "App".into(),
+        configuration: "Debug".into(),
+        device: None,
+        started_at: 0,
+        duration,
+        success,
+        warnings: 2,
+        errors: 0,
+        trigger: None,
+    };
+    let mut records = (0..TREND_WINDOW)
+        .map(|_| record(1000, true))
+        .collect::<Vec<_>>();
+    records.push(record(100, false));
+    records.extend((0..TREND_WINDOW).map(|_| record(1500, true)));
+
+    let history = BuildHistory::new(records, Some("App"));
+    let stats = &history.stats[0];
+
+    assert_eq!(stats.builds, TREND_WINDOW * 2 + 1);
+    assert_eq!(stats.failures, 1);
+    assert_eq!(stats.average_duration, 1250);
+    assert_eq!(stats.average_warnings, 2.0);
+    assert_eq!(stats.trend, Some(50.0));
+}
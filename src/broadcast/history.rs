@@ -0,0 +1,78 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use typescript_type_def::TypeDef;
+
+/// Maximum number of messages kept in project history
+const HISTORY_CAPACITY: usize = 1000;
+
+/// Filter of project messages replayed to a newly registered client
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TypeDef)]
+pub struct ReplayFilter {
+    /// Only replay messages with at least the given level
+    #[serde(default)]
+    pub level: Option<ContentLevel>,
+    /// Only replay messages of a given task, see [`TaskInfo`]
+    #[serde(default)]
+    pub task: Option<usize>,
+}
+
+impl ReplayFilter {
+    fn matches(&self, record: &Record) -> bool {
+        let level = match (&self.level, &record.level) {
+            (Some(min), Some(level)) => level >= min,
+            _ => true,
+        };
+        let task = self.task.is_none() || self.task == record.task;
+        level && task
+    }
+}
+
+/// Serialized project message kept for replay
+#[derive(Debug)]
+struct Record {
+    /// Id of the task the message was sent from
+    task: Option<usize>,
+    level: Option<ContentLevel>,
+    capability: Option<&'static str>,
+    line: String,
+}
+
+/// Bounded ring buffer of recent project log and task messages
+#[derive(Debug, Default)]
+pub(crate) struct History(VecDeque<Record>);
+
+impl History {
+    /// Record a broadcasted message, the oldest message is dropped once full
+    pub(crate) fn push(
+        &mut self,
+        task: Option<usize>,
+        message: &Message,
+        capability: Option<&'static str>,
+        line: &str,
+    ) {
+        if !message.is_replayable() {
+            return;
+        }
+
+        if self.0.len() == HISTORY_CAPACITY {
+            self.0.pop_front();
+        }
+
+        self.0.push_back(Record {
+            task,
+            level: message.level().cloned(),
+            capability,
+            line: line.to_string(),
+        });
+    }
+
+    /// Get recorded messages matching a given filter along with their capability
+    pub(crate) fn replay(&self, filter: &ReplayFilter) -> Vec<(Option<&'static str>, String)> {
+        self.0
+            .iter()
+            .filter(|record| filter.matches(record))
+            .map(|record| (record.capability, record.line.clone()))
+            .collect()
+    }
+}
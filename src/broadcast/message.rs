@@ -1,9 +1,11 @@
-use crate::{BuildSettings, ProjectInfo, Runners};
+use crate::{BuildSettings, ProjectInfo, ReplayFilter, Runners};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use strum::EnumVariantNames;
 use typescript_type_def::TypeDef;
 
 /// State usesd to set client state
-#[derive(Debug, Serialize, TypeDef)]
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
 #[serde(tag = "key", content = "value")]
 #[serde(rename_all = "camelCase")]
 pub enum State {
@@ -12,7 +14,7 @@ pub enum State {
 }
 
 /// Representation of Messages that clients needs to process
-#[derive(Debug, Serialize, TypeDef)]
+#[derive(Debug, Serialize, Deserialize, TypeDef, EnumVariantNames)]
 #[serde(tag = "type", content = "args")]
 pub enum Message {
     /// Notify use with a message
@@ -51,9 +53,73 @@ pub enum Message {
     SetState(State),
     /// Internal!
     #[serde(skip)]
+    Connect(Option<ReplayFilter>),
+    /// Internal!
+    #[serde(skip)]
+    Attach,
+    /// Internal!
+    #[serde(skip)]
     Disconnect,
 }
 
+/// Capabilities clients can declare in their hello request, see [`Message::capability`]
+pub const CAPABILITIES: &[&str] = &["task_kind_resolve", "task_kind_command"];
+
+/// Message pushed on client connection for a registered project root
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct Notification {
+    pub root: PathBuf,
+    pub message: Message,
+    /// Id of the task the message belongs to, see [`TaskInfo`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<usize>,
+}
+
+impl Message {
+    /// Capability a client must declare in its hello request to receive this message.
+    ///
+    /// Messages added after protocol v1 should be gated here, so older clients don't receive
+    /// message kinds they can't process. Other messages of a gated task are gated by the
+    /// broadcaster too, see [`Broadcast`].
+    pub fn capability(&self) -> Option<&'static str> {
+        match self {
+            Self::SetCurrentTask { kind, .. } => kind.capability(),
+            Self::Notify { .. }
+            | Self::Log { .. }
+            | Self::OpenLogger
+            | Self::ReloadLspServer
+            | Self::UpdateCurrentTask { .. }
+            | Self::FinishCurrentTask { .. }
+            | Self::SetWatching { .. }
+            | Self::SetState(_)
+            | Self::Connect(_)
+            | Self::Attach
+            | Self::Disconnect => None,
+        }
+    }
+
+    /// Whether the message is kept in project history to be replayed to new clients
+    pub fn is_replayable(&self) -> bool {
+        matches!(
+            self,
+            Self::Log { .. }
+                | Self::SetCurrentTask { .. }
+                | Self::UpdateCurrentTask { .. }
+                | Self::FinishCurrentTask { .. }
+        )
+    }
+
+    /// Get content level of the message if any
+    pub fn level(&self) -> Option<&ContentLevel> {
+        match self {
+            Self::Notify { level, .. }
+            | Self::Log { level, .. }
+            | Self::UpdateCurrentTask { level, .. } => Some(level),
+            _ => None,
+        }
+    }
+}
+
 /// What kind of task is currently under progress?
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TypeDef)]
 pub enum TaskKind {
@@ -65,6 +131,21 @@ pub enum TaskKind {
     Compile,
     /// Generate xcodeproj
     Generate,
+    /// Resolve project dependencies, e.g. `tuist install` or `swift package resolve`
+    Resolve,
+    /// User defined command, see [`crate::CommandConfig`]
+    Command,
+}
+
+impl TaskKind {
+    /// Capability a client must declare to receive messages of tasks of this kind
+    pub fn capability(&self) -> Option<&'static str> {
+        match self {
+            Self::Build | Self::Run | Self::Compile | Self::Generate => None,
+            Self::Resolve => Some("task_kind_resolve"),
+            Self::Command => Some("task_kind_command"),
+        }
+    }
 }
 
 /// What the status of task is currently under progress?
@@ -1,175 +1,157 @@
+mod build_history;
+mod history;
 mod message;
 mod task;
+mod task_log;
 
 pub use self::message::*;
+pub use build_history::*;
+pub use history::*;
 pub use task::*;
+pub use task_log::*;
 use tracing::instrument;
 
+use crate::server::push_message;
 use crate::util::extensions::PathExt;
-use crate::Result;
 use process_stream::*;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::{UnixListener, UnixStream};
-use tokio::sync::{mpsc::*, Mutex, Notify};
+use tokio::sync::{mpsc::*, Notify};
 use tokio::task::JoinHandle;
 
-/// Broadcast server to send task to clients
+/// State of a client session subscribed to project messages
+#[derive(Debug)]
+enum Listener {
+    /// Subscribed but not yet ready, messages are queued along with their capability
+    Pending(Vec<(Option<&'static str>, String)>),
+    /// Ready to receive messages
+    Attached,
+}
+
+/// Broadcast project messages to subscribed client sessions
 #[derive(Debug)]
 pub struct Broadcast {
     /// Project root for which the logger is created for.
     root: PathBuf,
-    /// Logger path
-    address: PathBuf,
     /// Logger handler
     pub handle: JoinHandle<()>,
-    /// Server handler
-    pub server: JoinHandle<()>,
     /// Sender to be used within the server to write items to file_path
-    tx: UnboundedSender<(Option<u32>, Message)>,
+    tx: UnboundedSender<(Option<u32>, Option<usize>, Message)>,
     /// Abort notifier to stop the logger
     abort: Arc<Notify>,
-    /// Socket listeners
-    #[allow(dead_code)]
-    listeners: Arc<Mutex<HashMap<u32, UnixStream>>>,
 }
 
 impl Broadcast {
-    pub const ROOT: &'static str = "/private/tmp/xbase";
-
     #[instrument(parent = None, name = "Broadcaster", skip_all, fields(name = root.as_ref().name().unwrap()))]
-    pub async fn new(root: impl AsRef<Path>) -> Result<Self> {
+    pub fn new(root: impl AsRef<Path>) -> Self {
         let (tx, rx) = unbounded_channel();
-        let name = format!("{}.socket", root.as_ref().unique_name().unwrap());
-        let base = PathBuf::from(Self::ROOT);
-
-        if !base.exists() {
-            tokio::fs::create_dir(Self::ROOT).await?;
-        }
-
-        let address = base.join(name);
-        let name = root.as_ref().name().unwrap();
-
-        if address.exists() {
-            tokio::fs::remove_file(&address).await.ok();
-        };
-
+        let root = root.as_ref().to_path_buf();
         let abort: Arc<Notify> = Default::default();
-        let listeners: Arc<Mutex<HashMap<u32, UnixStream>>> = Default::default();
-
-        let listener = UnixListener::bind(&address)?;
-        let server = tokio::spawn(Self::start_server(
-            name.clone(),
-            listener,
-            abort.clone(),
-            listeners.clone(),
-        ));
         let handle = tokio::spawn(Self::start_messages_handler(
-            name,
+            root.clone(),
             rx,
             abort.clone(),
-            listeners.clone(),
         ));
 
         tracing::info!("Created");
 
-        Ok(Self {
-            root: root.as_ref().to_path_buf(),
+        Self {
+            root,
             tx,
             abort,
             handle,
-            listeners,
-            server,
-            address,
-        })
-    }
-
-    /// Start Broadcast server and start accepting clients
-    #[instrument(parent = None, name = "Broadcaster", skip_all, fields(name=name))]
-    async fn start_server(
-        name: String,
-        listener: UnixListener,
-        abort: Arc<Notify>,
-        listeners: Arc<Mutex<HashMap<u32, UnixStream>>>,
-    ) {
-        loop {
-            tokio::select! {
-                _ = abort.notified() => {
-                    tracing::info!("[Dropped]");
-                    break
-                },
-                Ok((mut stream, _)) = listener.accept() => {
-                    let mut listeners = listeners.lock().await;
-
-                    let (reader, _) = stream.split();
-
-                    let mut buf = String::default();
-                    let mut reader = BufReader::new(reader);
-
-                    // let reader = BufReader::new(&stream);
-                    match reader.read_line(&mut buf).await {
-                        Ok(_) => match buf.trim().parse::<u32>() {
-                            Ok(id) =>  {
-                                tracing::info!("Connected [{id}]");
-                                listeners.insert(id, stream);
-                            }
-                            Err(err) => {
-                                tracing::error!("Failed to parse id as u32: {err}");
-
-                            }
-                        }
-                        Err(err) => {
-                            tracing::error!("Failed to connect a client: {err}");
-                        }
-                    }
-                }
-            }
         }
     }
 
     /// Start message handler
-    /// This loop receive messages and write them on all connected clients.
-    #[instrument(parent = None, name = "Broadcaster", skip_all, fields(name=name))]
+    /// This loop receive messages and write them on subscribed client sessions.
+    #[instrument(parent = None, name = "Broadcaster", skip_all, fields(name = root.name().unwrap()))]
     async fn start_messages_handler(
-        name: String,
-        mut rx: UnboundedReceiver<(Option<u32>, Message)>,
+        root: PathBuf,
+        mut rx: UnboundedReceiver<(Option<u32>, Option<usize>, Message)>,
         abort: Arc<Notify>,
-        listeners: Arc<Mutex<HashMap<u32, UnixStream>>>,
     ) {
+        let mut listeners = HashMap::new();
+        let mut history = History::default();
+        // Capabilities of tasks whose messages are gated, see [`Message::capability`]
+        let mut gated_tasks: HashMap<usize, &'static str> = HashMap::new();
         loop {
             tokio::select! {
                 _ = abort.notified() => { break; },
                 result = rx.recv() => match result {
                     None => break,
-                    Some((id, message)) => {
-                        let listeners =  listeners.clone();
-                        let mut listeners = listeners.lock().await;
-                        if let Message::Disconnect = message {
-                            listeners.remove(&id.unwrap());
-                            continue;
+                    Some((id, task, message)) => {
+                        match (id, &message) {
+                            (Some(id), Message::Connect(replay)) => {
+                                tracing::info!("Connected [{id}]");
+                                let queue = replay
+                                    .as_ref()
+                                    .map(|filter| history.replay(filter))
+                                    .unwrap_or_default();
+                                listeners.insert(id, Listener::Pending(queue));
+                                continue;
+                            }
+                            (Some(id), Message::Attach) => {
+                                let Some(listener) = listeners.get_mut(&id) else { continue };
+                                let listener = std::mem::replace(listener, Listener::Attached);
+                                if let Listener::Pending(queue) = listener {
+                                    tracing::info!("Attached [{id}]");
+                                    for (capability, line) in queue {
+                                        push_message(id, capability, &line);
+                                    }
+                                }
+                                continue;
+                            }
+                            (Some(id), Message::Disconnect) => {
+                                listeners.remove(&id);
+                                continue;
+                            }
+                            _ => {}
                         }
 
-                        match serde_json::to_string(&message) {
-                            Ok(mut value) => {
-                                tracing::trace!("{value}");
-                                value.push('\n');
-                                if let Some(id) = id {
-                                    if let Some(stream) = listeners.get_mut(&id) {
-                                        stream.write_all(value.as_bytes()).await.ok();
-                                        stream.flush().await.ok();
-                                    } else {
-                                        tracing::error!("[CLIENT WITH {id} NOT FOUND]")
-                                    }
-                                } else {
-                                    for (_, listener) in listeners.iter_mut() {
-                                        listener.write_all(value.as_bytes()).await.ok();
-                                        listener.flush().await.ok();
-                                    };
+                        let capability = match (task, message.capability()) {
+                            (Some(task), Some(capability)) => {
+                                gated_tasks.insert(task, capability);
+                                Some(capability)
+                            }
+                            (Some(task), None)
+                                if matches!(message, Message::FinishCurrentTask { .. }) =>
+                            {
+                                gated_tasks.remove(&task)
+                            }
+                            (Some(task), None) => gated_tasks.get(&task).copied(),
+                            (None, capability) => capability,
+                        };
+                        let notification = Notification {
+                            root: root.clone(),
+                            message,
+                            task,
+                        };
+                        let line = match serde_json::to_string(&notification) {
+                            Ok(line) => line,
+                            Err(err) => {
+                                tracing::warn!("SendError: `{notification:?}` = `{err}`");
+                                continue;
+                            }
+                        };
+                        tracing::trace!("{line}");
+
+                        if let Some(id) = id {
+                            if let Some(Listener::Pending(queue)) = listeners.get_mut(&id) {
+                                queue.push((capability, line));
+                            } else if !push_message(id, capability, &line) {
+                                tracing::error!("[CLIENT WITH {id} NOT FOUND]")
+                            }
+                        } else {
+                            history.push(task, &notification.message, capability, &line);
+                            listeners.retain(|id, listener| match listener {
+                                Listener::Pending(queue) => {
+                                    queue.push((capability, line.clone()));
+                                    true
                                 }
-                            },
-                            Err(err) => tracing::warn!("SendError: `{message:?}` = `{err}`"),
+                                Listener::Attached => push_message(*id, capability, &line),
+                            });
                         }
                     }
                 }
@@ -178,7 +160,12 @@ impl Broadcast {
     }
 
     pub fn send(&self, id: Option<u32>, message: Message) {
-        self.tx.send((id, message)).ok();
+        self.tx.send((id, None, message)).ok();
+    }
+
+    /// Send message to all clients on behalf of a given task
+    pub fn send_with_task(&self, task: usize, message: Message) {
+        self.tx.send((None, Some(task), message)).ok();
     }
 
     /// Explicitly Abort/Consume logger
@@ -192,11 +179,19 @@ impl Broadcast {
         &self.root
     }
 
-    /// Get a reference to the logger's log path.
-    #[must_use]
-    pub fn address(&self) -> &PathBuf {
-        &self.address
+    /// Subscribe a client session to project messages, replaying recent messages matching the
+    /// given filter if any.
+    ///
+    /// Messages are queued till the session is attached with [`Broadcast::attach`].
+    pub fn subscribe(&self, id: u32, replay: Option<ReplayFilter>) {
+        self.send(Some(id), Message::Connect(replay))
+    }
+
+    /// Start writing queued and new project messages to a subscribed client session
+    pub fn attach(&self, id: u32) {
+        self.send(Some(id), Message::Attach)
     }
+
     /// Tell connected clients to open logger
     pub fn open_logger(&self) {
         self.send(None, Message::OpenLogger)
@@ -217,9 +212,10 @@ impl Broadcast {
         )
     }
 
-    pub fn finish_current_task(&self, success: bool) {
-        self.send(
-            None,
+    /// Finish a given task, see [`TaskInfo`]
+    pub fn finish_current_task(&self, task: usize, success: bool) {
+        self.send_with_task(
+            task,
             Message::FinishCurrentTask {
                 status: if success {
                     TaskStatus::Succeeded
@@ -1,4 +1,56 @@
 use super::*;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use typescript_type_def::TypeDef;
+
+/// Tasks currently under progress indexed by task id
+static ACTIVE_TASKS: Lazy<std::sync::Mutex<HashMap<usize, TaskInfo>>> = Lazy::new(Default::default);
+
+/// Last created task id
+static TASK_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Information about a task under progress
+#[derive(Debug, Clone, Serialize, Deserialize, TypeDef)]
+pub struct TaskInfo {
+    pub id: usize,
+    pub kind: TaskKind,
+    pub target: String,
+    pub root: PathBuf,
+}
+
+/// Task registration in active tasks, removed on finish or once all task handles are dropped
+#[derive(Debug)]
+struct TaskRegistration(usize);
+
+impl TaskRegistration {
+    fn new(info: TaskInfo) -> Self {
+        let id = info.id;
+        active_tasks_mut().insert(id, info);
+        Self(id)
+    }
+
+    fn remove(&self) {
+        active_tasks_mut().remove(&self.0);
+    }
+}
+
+impl Drop for TaskRegistration {
+    fn drop(&mut self) {
+        self.remove()
+    }
+}
+
+fn active_tasks_mut() -> std::sync::MutexGuard<'static, HashMap<usize, TaskInfo>> {
+    ACTIVE_TASKS.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Get tasks that are currently under progress
+pub fn active_tasks() -> Vec<TaskInfo> {
+    let mut tasks = active_tasks_mut().values().cloned().collect::<Vec<_>>();
+    tasks.sort_by_key(|t| t.id);
+    tasks
+}
 
 #[derive(Debug, Clone)]
 pub struct Task {
@@ -7,33 +59,82 @@ pub struct Task {
     #[allow(dead_code)]
     target: String,
     inner: Arc<Broadcast>,
+    registration: Arc<TaskRegistration>,
+    /// Log file of task output, see [`task_log_path`]
+    log: Option<Arc<TaskLog>>,
+    /// Build to be recorded in project build history once finished
+    build: Option<Arc<BuildTracker>>,
 }
 
 impl Task {
     /// Create a new task with it's kind, the target, and broadcast to send message through
     pub fn new(task: TaskKind, target: &str, broadcast: Arc<Broadcast>) -> Task {
-        broadcast
-            .tx
-            .send((
-                None,
-                Message::SetCurrentTask {
-                    kind: task.clone(),
-                    target: target.into(),
-                    status: TaskStatus::Processing,
-                },
-            ))
-            .ok();
+        let log = TaskLog::create(broadcast.root(), &task, target)
+            .map_err(|err| tracing::warn!("Failed to create {task:?} log for {target}: {err}"))
+            .ok()
+            .map(Arc::new);
+        let registration = TaskRegistration::new(TaskInfo {
+            id: TASK_ID.fetch_add(1, Ordering::SeqCst) + 1,
+            kind: task.clone(),
+            target: target.into(),
+            root: broadcast.root().clone(),
+        });
+        broadcast.send_with_task(
+            registration.0,
+            Message::SetCurrentTask {
+                kind: task.clone(),
+                target: target.into(),
+                status: TaskStatus::Processing,
+            },
+        );
         Task {
             task,
             target: target.into(),
             inner: broadcast,
+            registration: Arc::new(registration),
+            log,
+            build: None,
+        }
+    }
+
+    /// Record task in project build history once finished
+    pub fn track_build(mut self, tracker: BuildTracker) -> Self {
+        self.build = Some(Arc::new(tracker));
+        self
+    }
+
+    /// Write a line to task log file without sending it to clients
+    fn write_log<S: AsRef<str>>(&self, line: S) {
+        if let Some(log) = self.log.as_ref() {
+            log.write(line);
+        }
+    }
+
+    /// Write unfiltered process output to task raw log file
+    fn write_raw_log<S: AsRef<str>>(&self, line: S) {
+        if let Some(log) = self.log.as_ref() {
+            log.write_raw(line);
         }
     }
 
     fn update<S: AsRef<str>>(&self, level: ContentLevel, content: S) {
+        self.write_log(&content);
+        if let Some(build) = self.build.as_ref() {
+            build.count(&level);
+        }
         let content = content.as_ref().into();
         let message = Message::UpdateCurrentTask { content, level };
-        self.inner.tx.send((None, message)).ok();
+        self.inner.send_with_task(self.id(), message);
+    }
+
+    /// Get task log, used to keep logging output of processes outliving the task
+    pub fn log(&self) -> Option<Arc<TaskLog>> {
+        self.log.clone()
+    }
+
+    /// Get task id, see [`TaskInfo`]
+    pub fn id(&self) -> usize {
+        self.registration.0
     }
 
     /// Update CurrentTask with info and content
@@ -63,26 +164,41 @@ impl Task {
     /// Finish task with whether it was successfull or not
     pub fn finish(&self, success: bool) {
         tracing::trace!("Finishing {:?}: success: {success}", self.task);
+        self.registration.remove();
+        self.write_log(if success { "[Succeeded]" } else { "[Failed]" });
+        if let Some(build) = self.build.as_ref() {
+            build.finish(self.inner.root(), success);
+        }
 
-        self.inner
-            .tx
-            .send((
-                None,
-                Message::FinishCurrentTask {
-                    status: if success {
-                        TaskStatus::Succeeded
-                    } else {
-                        TaskStatus::Failed
-                    },
+        self.inner.send_with_task(
+            self.id(),
+            Message::FinishCurrentTask {
+                status: if success {
+                    TaskStatus::Succeeded
+                } else {
+                    TaskStatus::Failed
                 },
-            ))
-            .ok();
+            },
+        );
 
         if !success {
             self.inner.open_logger();
         }
     }
 
+    /// Finish task that was cancelled before completion, cancelled builds aren't recorded
+    pub fn cancel(&self) {
+        tracing::trace!("Cancelling {:?}", self.task);
+        self.registration.remove();
+        self.write_log("[Cancelled]");
+        self.inner.send_with_task(
+            self.id(),
+            Message::FinishCurrentTask {
+                status: TaskStatus::Failed,
+            },
+        );
+    }
+
     pub fn consume(&self, mut process: Box<dyn ProcessExt + Send>) -> Result<Receiver<bool>> {
         let mut stream = process.spawn_and_stream()?;
         let cancel = self.inner.abort.clone();
@@ -99,8 +215,15 @@ impl Task {
                         send_status.send(false).await.unwrap_or_default();
                         break;
                     },
+                    // Status receiver dropped, e.g. in-flight watch build was cancelled
+                    _ = send_status.closed() => {
+                        abort.notify_one();
+                        this.cancel();
+                        break;
+                    },
                     result = stream.next() => match result {
                         Some(output) => {
+                            this.write_raw_log(output.to_string());
                             if let Some(succ) = output.is_success() {
                                 tracing::trace!("{output:?}");
                                 this.finish(succ);
@@ -113,10 +236,10 @@ impl Task {
                                     this.error(content)
                                 } else if content.to_lowercase().contains("warn") {
                                     this.warn(content)
+                                } else if content != "Resolving Packages" {
+                                    this.info(content)
                                 } else {
-                                    if content != "Resolving Packages" {
-                                        this.info(content)
-                                    };
+                                    this.write_log(content)
                                 }
                             }
                         }
@@ -0,0 +1,189 @@
+use super::*;
+use crate::util::fs::get_build_cache_dir;
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{LineWriter, Write};
+use std::time::UNIX_EPOCH;
+use typescript_type_def::TypeDef;
+
+/// Number of logs kept for each task kind and target, older logs are rotated out
+const MAX_TASK_LOGS: usize = 5;
+
+/// Past task log file
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct TaskLogInfo {
+    /// Log file name, e.g. `Build_App.log` for latest log and `Build_App.1.log` for the one before
+    pub name: String,
+    /// Whether the log holds unfiltered process output, e.g. `Build_App.raw.log`
+    pub raw: bool,
+    pub path: PathBuf,
+    /// Size in bytes
+    pub size: u64,
+    /// Last modification time in seconds since unix epoch
+    pub modified: u64,
+}
+
+/// Get directory where task logs of a given project root are located
+pub fn task_logs_dir(root: &Path) -> Result<PathBuf> {
+    Ok(PathBuf::from(get_build_cache_dir(root)?).join("Logs"))
+}
+
+/// Get path to where the latest log of a given task kind and target will be located
+pub fn task_log_path(root: &Path, kind: &TaskKind, target: &str) -> Result<PathBuf> {
+    Ok(task_logs_dir(root)?.join(task_log_name(kind, target)))
+}
+
+/// Get file name of the latest log of a given task kind and target
+fn task_log_name(kind: &TaskKind, target: &str) -> String {
+    format!("{kind:?}_{target}.log").replace(' ', "_")
+}
+
+/// Get path to where unfiltered process output of the latest task will be located, next to
+/// [`task_log_path`]
+pub fn task_raw_log_path(root: &Path, kind: &TaskKind, target: &str) -> Result<PathBuf> {
+    Ok(task_log_path(root, kind, target)?.with_extension("raw.log"))
+}
+
+/// Get task logs of a given project root, newest first
+pub fn task_logs(root: &Path) -> Result<Vec<TaskLogInfo>> {
+    logs_in(&task_logs_dir(root)?)
+}
+
+/// Get task logs within a given directory, newest first
+fn logs_in(dir: &Path) -> Result<Vec<TaskLogInfo>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut logs = fs::read_dir(dir)?
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+            let name = entry.file_name().to_str()?.to_string();
+            Some(TaskLogInfo {
+                raw: name.contains(".raw."),
+                name,
+                path: entry.path(),
+                size: metadata.len(),
+                modified: modified.as_secs(),
+            })
+        })
+        .collect::<Vec<_>>();
+    logs.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.name.cmp(&b.name)));
+
+    Ok(logs)
+}
+
+/// Writer of task output to log files within project cache directory.
+///
+/// Formatted output sent to clients is written to [`task_log_path`] while unfiltered process
+/// output is written to [`task_raw_log_path`]. Creating a new log rotates previous logs of the
+/// same task kind and target, keeping the last [`MAX_TASK_LOGS`].
+#[derive(Debug)]
+pub struct TaskLog {
+    log: std::sync::Mutex<LineWriter<File>>,
+    raw: std::sync::Mutex<LineWriter<File>>,
+}
+
+impl TaskLog {
+    pub fn create(root: &Path, kind: &TaskKind, target: &str) -> Result<Self> {
+        Self::create_in(&task_logs_dir(root)?, kind, target)
+    }
+
+    /// Create task log within a given directory instead of project cache directory
+    pub fn create_in(dir: &Path, kind: &TaskKind, target: &str) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(task_log_name(kind, target));
+        let create = |path: PathBuf| -> Result<_> {
+            rotate(&path);
+            let mut file = LineWriter::new(File::create(&path)?);
+            writeln!(file, "[{kind:?}] {target}")?;
+            Ok(std::sync::Mutex::new(file))
+        };
+
+        Ok(Self {
+            raw: create(path.with_extension("raw.log"))?,
+            log: create(path)?,
+        })
+    }
+
+    /// Write a line to the log file
+    pub fn write<S: AsRef<str>>(&self, line: S) {
+        write_line(&self.log, line.as_ref())
+    }
+
+    /// Write a line of unfiltered process output to the raw log file
+    pub fn write_raw<S: AsRef<str>>(&self, line: S) {
+        write_line(&self.raw, line.as_ref())
+    }
+}
+
+fn write_line(file: &std::sync::Mutex<LineWriter<File>>, line: &str) {
+    let mut file = file.lock().unwrap_or_else(|err| err.into_inner());
+    if let Err(err) = writeln!(file, "{line}") {
+        tracing::warn!("Failed to write task log: {err}");
+    }
+}
+
+/// Shift `name.log` to `name.1.log`, `name.1.log` to `name.2.log` and so on
+fn rotate(path: &Path) {
+    let rotated = |n: usize| match n {
+        0 => path.to_path_buf(),
+        n => path.with_extension(format!("{n}.log")),
+    };
+
+    for n in (1..MAX_TASK_LOGS).rev() {
+        let from = rotated(n - 1);
+        if from.exists() {
+            fs::rename(&from, rotated(n)).ok();
+        }
+    }
+}
+
+#[test]
+fn test_rotate_task_logs() {
+    let dir = std::env::temp_dir().join("xbase-test-rotate");
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("Build_App.log");
+
+    for n in 0..MAX_TASK_LOGS + 2 {
+        rotate(&path);
+        fs::write(&path, n.to_string()).unwrap();
+    }
+
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), MAX_TASK_LOGS);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        (MAX_TASK_LOGS + 1).to_string()
+    );
+    let oldest = dir.join(format!("Build_App.{}.log", MAX_TASK_LOGS - 1));
+    assert_eq!(fs::read_to_string(oldest).unwrap(), "2");
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_task_log_raw_output() {
+    let dir = std::env::temp_dir().join("xbase-test-raw-log");
+    fs::remove_dir_all(&dir).ok();
+    let log = TaskLog::create_in(&dir, &TaskKind::Build, "App").unwrap();
+    log.write("[Info] Compiling App.swift");
+    log.write_raw("CompileSwift normal arm64 /App/App.swift (in target 'App')");
+    drop(log);
+
+    let logs = logs_in(&dir).unwrap();
+    let raw = logs.iter().find(|log| log.raw).unwrap();
+    assert_eq!(raw.name, "Build_App.raw.log");
+    assert!(fs::read_to_string(&raw.path)
+        .unwrap()
+        .contains("CompileSwift normal"));
+    let log = logs.iter().find(|log| log.name == "Build_App.log").unwrap();
+    assert!(!fs::read_to_string(&log.path)
+        .unwrap()
+        .contains("CompileSwift"));
+
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,111 @@
+//! Client to communicate with a running xbase daemon
+mod output;
+
+pub use output::*;
+
+use crate::server::{Request, Response};
+use crate::*;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::path::Path;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+
+/// Connection to xbase daemon socket
+pub struct Client {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+    last_id: u32,
+    /// Notifications received while waiting for a response
+    notifications: VecDeque<Notification>,
+}
+
+impl Client {
+    /// Connect to daemon listening at a given socket address
+    pub async fn connect(address: impl AsRef<Path>) -> Result<Self> {
+        let (reader, writer) = UnixStream::connect(address).await?.into_split();
+        Ok(Self {
+            lines: BufReader::new(reader).lines(),
+            writer,
+            last_id: 0,
+            notifications: Default::default(),
+        })
+    }
+
+    /// Send a request and wait for its response data
+    pub async fn request(&mut self, request: &Request) -> Result<Value> {
+        self.last_id += 1;
+        let mut message = serde_json::to_value(request)?;
+        message["id"] = self.last_id.into();
+
+        let mut line = serde_json::to_string(&message)?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).await?;
+        self.writer.flush().await?;
+
+        self.read_response(self.last_id).await?.into_result()
+    }
+
+    /// Send a request and deserialize response data into `T`
+    pub async fn request_as<T: DeserializeOwned>(&mut self, request: &Request) -> Result<T> {
+        Ok(serde_json::from_value(self.request(request).await?)?)
+    }
+
+    /// Wait for next project message pushed by the daemon, returns None once disconnected
+    pub async fn next_notification(&mut self) -> Result<Option<Notification>> {
+        if let Some(notification) = self.notifications.pop_front() {
+            return Ok(Some(notification));
+        }
+
+        loop {
+            match self.read_line().await? {
+                Some(Incoming::Notification(notification)) => return Ok(Some(notification)),
+                Some(Incoming::Response(response)) => {
+                    tracing::debug!("Ignoring response of request {}", response.id)
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Read lines till the response of a given request id is received
+    async fn read_response(&mut self, id: u32) -> Result<Response> {
+        loop {
+            match self.read_line().await? {
+                Some(Incoming::Response(response)) if response.id == id => return Ok(response),
+                Some(Incoming::Response(response)) => {
+                    tracing::debug!("Ignoring response of request {}", response.id)
+                }
+                Some(Incoming::Notification(notification)) => {
+                    self.notifications.push_back(notification)
+                }
+                None => return Err(Error::Unexpected("Connection closed by daemon".into())),
+            }
+        }
+    }
+
+    /// Read next non-empty line, returns None once the connection is closed
+    async fn read_line(&mut self) -> Result<Option<Incoming>> {
+        while let Some(line) = self.lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value: Value = serde_json::from_str(&line)?;
+            let incoming = if value.get("root").is_some() {
+                Incoming::Notification(serde_json::from_value(value)?)
+            } else {
+                Incoming::Response(serde_json::from_value(value)?)
+            };
+            return Ok(Some(incoming));
+        }
+        Ok(None)
+    }
+}
+
+/// Line written by the daemon on client connection
+enum Incoming {
+    Response(Response),
+    Notification(Notification),
+}
@@ -0,0 +1,89 @@
+use crate::*;
+
+/// How messages should be printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable lines
+    Human,
+    /// A JSON object per line
+    Json,
+}
+
+/// Print broadcast messages to the terminal
+#[derive(Debug)]
+pub struct Output {
+    format: OutputFormat,
+    verbose: bool,
+    current_task: Option<(TaskKind, String)>,
+}
+
+impl Output {
+    pub fn new(format: OutputFormat, verbose: bool) -> Self {
+        Self {
+            format,
+            verbose,
+            current_task: None,
+        }
+    }
+
+    /// Get the kind and target of the last started task
+    pub fn current_task(&self) -> Option<&(TaskKind, String)> {
+        self.current_task.as_ref()
+    }
+
+    /// Print a message and keep track of current task
+    pub fn print(&mut self, message: &Message) {
+        if let Message::SetCurrentTask { kind, target, .. } = message {
+            self.current_task = Some((kind.clone(), target.clone()));
+        }
+
+        if self.format == OutputFormat::Json {
+            match serde_json::to_string(message) {
+                Ok(line) => println!("{line}"),
+                Err(err) => eprintln!("{err}"),
+            }
+            return;
+        }
+
+        match message {
+            Message::Notify { content, level } | Message::Log { content, level } => {
+                self.print_content(content, level)
+            }
+            Message::UpdateCurrentTask { content, level } => self.print_content(content, level),
+            Message::SetCurrentTask { kind, target, .. } => {
+                println!("[{target}] {kind:?} started")
+            }
+            Message::FinishCurrentTask { status } => {
+                if let Some((kind, target)) = &self.current_task {
+                    println!("[{target}] {kind:?} {status:?}");
+                }
+            }
+            Message::SetWatching { watching, settings } => {
+                let state = if *watching {
+                    "Watching"
+                } else {
+                    "Stopped watching"
+                };
+                println!("[{}] {state}", settings.target)
+            }
+            Message::SetState(_)
+            | Message::OpenLogger
+            | Message::ReloadLspServer
+            | Message::Connect(_)
+            | Message::Attach
+            | Message::Disconnect => {}
+        }
+    }
+
+    fn print_content(&self, content: &str, level: &ContentLevel) {
+        if content.is_empty() {
+            return;
+        }
+        match level {
+            ContentLevel::Trace | ContentLevel::Debug if !self.verbose => {}
+            ContentLevel::Trace | ContentLevel::Debug | ContentLevel::Info => println!("{content}"),
+            ContentLevel::Warn => eprintln!("warning: {content}"),
+            ContentLevel::Error => eprintln!("error: {content}"),
+        }
+    }
+}
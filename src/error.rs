@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+#[cfg(not(target_os = "macos"))]
+use crate::simctl;
 use serde::{Deserialize, Serialize};
 use thiserror::Error as ThisError;
 use typescript_type_def::TypeDef;
@@ -24,6 +26,8 @@ pub enum Error {
     Run(String),
     #[error("Failed to generate project definition")]
     Generate,
+    #[error("Failed to resolve project dependencies")]
+    Resolve,
     #[error("Failed to generate compile commands")]
     Compile,
     #[error("Failed to parse project definition: {0}")]
@@ -42,6 +46,12 @@ pub enum Error {
     MessageParse(String),
     #[error("{0} is not a registered project!")]
     UnknownProject(PathBuf),
+    #[error("Failed to discover devices: {0}")]
+    DeviceDiscovery(String),
+    #[error("Simulator operation failed: {0}")]
+    Simulator(String),
+    #[error("Incompatible protocol: {0}")]
+    IncompatibleProtocol(String),
 }
 
 impl From<ServerError> for Error {
@@ -51,11 +61,15 @@ impl From<ServerError> for Error {
             "Build" => Self::Build(v.msg),
             "Run" => Self::Run(v.msg),
             "Generate" => Self::Generate,
+            "Resolve" => Self::Resolve,
             "DefinitionParsing" => Self::DefinitionParsing(v.msg),
             "DefinitionLocating" => Self::DefinitionLocating,
             "DefinitionMutliFound" => Self::DefinitionMutliFound,
             "SendError" => Self::SendError(v.msg),
             "MessageParse" => Self::MessageParse(v.msg),
+            "DeviceDiscovery" => Self::DeviceDiscovery(v.msg),
+            "Simulator" => Self::Simulator(v.msg),
+            "IncompatibleProtocol" => Self::IncompatibleProtocol(v.msg),
             _ => Self::Unexpected(v.msg),
         }
     }
@@ -73,6 +87,7 @@ impl From<&Error> for ServerError {
             Error::Build(_) => res.kind = "Build".into(),
             Error::Run(_) => res.kind = "Run".into(),
             Error::Generate => res.kind = "Generate".into(),
+            Error::Resolve => res.kind = "Resolve".into(),
             Error::DefinitionParsing(_) => res.kind = "DefinitionParsing".into(),
             Error::DefinitionLocating => res.kind = "DefinitionLocating".into(),
             Error::DefinitionMutliFound => res.kind = "DefinitionMutliFound".into(),
@@ -82,6 +97,9 @@ impl From<&Error> for ServerError {
             Error::MessageParse(_) => res.kind = "MessageParse".into(),
             Error::Compile => res.kind = "Compile".into(),
             Error::UnknownProject(_) => res.kind = "UnknownProject".into(),
+            Error::DeviceDiscovery(_) => res.kind = "DeviceDiscovery".into(),
+            Error::Simulator(_) => res.kind = "Simulator".into(),
+            Error::IncompatibleProtocol(_) => res.kind = "IncompatibleProtocol".into(),
         };
         res
     }
@@ -167,19 +185,24 @@ impl<V> IntoResult<V> for Option<V> {
 
 impl From<simctl::Error> for Error {
     fn from(e: simctl::Error) -> Self {
-        Self::Run(match e {
-            simctl::Error::Output { stderr, .. } => stderr
-                .trim()
-                .split(":")
-                .skip(1)
-                .collect::<String>()
-                .replace("\n", " ")
-                .trim()
-                .into(),
-            simctl::Error::Io(err) => err.to_string(),
-            simctl::Error::Json(err) => err.to_string(),
-            simctl::Error::Utf8(err) => err.to_string(),
-        })
+        Self::Run(simctl_error_message(e))
+    }
+}
+
+/// Get readable message out of simctl error
+pub(crate) fn simctl_error_message(e: simctl::Error) -> String {
+    match e {
+        simctl::Error::Output { stderr, .. } => stderr
+            .trim()
+            .split(":")
+            .skip(1)
+            .collect::<String>()
+            .replace("\n", " ")
+            .trim()
+            .into(),
+        simctl::Error::Io(err) => err.to_string(),
+        simctl::Error::Json(err) => err.to_string(),
+        simctl::Error::Utf8(err) => err.to_string(),
     }
 }
 
@@ -1,9 +1,12 @@
 pub mod broadcast;
+pub mod client;
 pub mod error;
 pub mod project;
 mod runner;
 mod runtime;
 pub mod server;
+#[cfg(not(target_os = "macos"))]
+mod simctl;
 pub mod types;
 mod util;
 mod watcher;
@@ -17,15 +20,28 @@ pub use {
     broadcast::*, error::*, project::*, runner::*, runtime::*, types::*, util::*, watcher::*,
 };
 
-pub static SOCK_ADDR: &str = "/tmp/xbase.socket";
-pub static PID_PATH: &str = "/tmp/xbase.pid";
-pub static LOG_PATH: &str = "/tmp/xbase.log";
+/// Daemon socket file name within [`RUNTIME_DIR`]
+pub static SOCK_NAME: &str = "xbase.socket";
+/// Per-user directory holding daemon runtime files, see [`util::fs::runtime_dir`]
+pub static RUNTIME_DIR: Lazy<PathBuf> = Lazy::new(util::fs::runtime_dir);
+pub static SOCK_ADDR: Lazy<String> = Lazy::new(|| {
+    std::env::var(util::fs::SOCK_ADDR_ENV)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| runtime_path(SOCK_NAME))
+});
+pub static PID_PATH: Lazy<String> = Lazy::new(|| runtime_path("xbase.pid"));
+pub static LOG_PATH: Lazy<String> = Lazy::new(|| runtime_path("xbase.log"));
 pub static BIN_ROOT: &str = "$HOME/.local/share/xbase";
 
 pub type ProjectRuntimes = HashMap<PathBuf, PRMessageSender>;
 
 static RUNTIMES: Lazy<Arc<Mutex<ProjectRuntimes>>> = Lazy::new(Default::default);
 
+fn runtime_path(name: &str) -> String {
+    RUNTIME_DIR.join(name).display().to_string()
+}
+
 /// Get OwnedMutexGuard of runtimes
 #[tracing::instrument(name = "Runtimes")]
 pub async fn runtimes() -> OwnedMutexGuard<ProjectRuntimes> {
@@ -1,24 +1,135 @@
-use fs::cleanup_daemon_runtime;
+use client::{Client, Output, OutputFormat};
+use fs::{cleanup_daemon_runtime, ensure_runtime_dir};
 use futures::stream::StreamExt;
+use server::{
+    BuildRequest, DaemonStatus, HelloRequest, RegisterRequest, Request, RunRequest,
+    PROTOCOL_VERSION,
+};
 use signal_hook::consts::signal::*;
 use signal_hook_tokio::Signals;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{exit, Stdio};
+use std::time::Duration;
 use tokio::fs::write;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio::net::UnixListener;
 use tokio::{pin, select};
 use tracing::info;
 use tracing_setup::setup as tracing_setup;
 use xbase::*;
 
+static USAGE: &str = "Usage: xbase [COMMAND] [OPTIONS]
+
+Daemon Commands:
+  serve           Start the daemon in foreground (default)
+  stop            Gracefully stop the running daemon
+  status [--json] Print running daemon status
+  restart         Stop the running daemon and start a new one in background
+  logs [--follow] Print daemon logs
+  help            Print this message
+
+Project Commands:
+  register [ROOT]   Register project and print its messages till interrupted
+  build [ROOT]      Build a target and exit with its status
+  run [ROOT]        Build and run a target and exit with its status
+  watch [ROOT]      Build a target on every change, or run it with --device
+  devices           Print available devices
+
+Project Options:
+  -t, --target <TARGET>        Target to build/run
+  -c, --configuration <CONFIG> Configuration to build with [default: Debug]
+  -s, --scheme <SCHEME>        Scheme to build with
+  -d, --device <DEVICE>        Device name or id to run on
+  -w, --watch                  Keep building/running on changes
+  -j, --json                   Print messages as JSON lines
+  -v, --verbose                Print debug and trace messages";
+
+/// Flags that expect a value
+static VALUE_FLAGS: &[&str] = &[
+    "--target",
+    "-t",
+    "--configuration",
+    "-c",
+    "--scheme",
+    "-s",
+    "--device",
+    "-d",
+];
+
+/// Command line arguments following the command name
+struct Flags(Vec<String>);
+
+impl Flags {
+    /// Whether a flag is present
+    fn has(&self, long: &str, short: &str) -> bool {
+        self.0.iter().any(|f| f == long || f == short)
+    }
+
+    /// Get the value following a flag
+    fn value(&self, long: &str, short: &str) -> Option<String> {
+        let position = self.0.iter().position(|f| f == long || f == short)?;
+        self.0.get(position + 1).cloned()
+    }
+
+    /// Get first argument that is neither a flag nor a flag value
+    fn positional(&self) -> Option<String> {
+        let mut args = self.0.iter();
+        while let Some(arg) = args.next() {
+            if VALUE_FLAGS.contains(&arg.as_str()) {
+                args.next();
+            } else if !arg.starts_with('-') {
+                return Some(arg.clone());
+            }
+        }
+        None
+    }
+}
+
 #[tokio::main]
-// TODO: store futures somewhere, to gracefully close connection to clients
 async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let command = args.next();
+    let flags = Flags(args.collect());
+
+    let result = match command.as_deref() {
+        None | Some("serve") => return serve().await,
+        Some("stop") => stop().await,
+        Some("status") => status(flags.has("--json", "-j")).await,
+        Some("restart") => restart().await,
+        Some("logs") => logs(flags.has("--follow", "-f")).await,
+        Some("devices") => devices().await,
+        Some(command @ ("register" | "build" | "run" | "watch")) => project(command, &flags).await,
+        Some("help" | "--help" | "-h") => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Some(command) => {
+            eprintln!("Unknown command `{command}`\n\n{USAGE}");
+            exit(2)
+        }
+    };
+
+    if let Err(err) = result {
+        eprintln!("{err}");
+        exit(1)
+    }
+
+    Ok(())
+}
+
+// TODO: store futures somewhere, to gracefully close connection to clients
+async fn serve() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    once_cell::sync::Lazy::force(&server::STARTED_AT);
     let os_signal_handler = tokio::spawn(handle_os_signals());
+    tokio::spawn(server::watch_devices(DEVICES_REFRESH_INTERVAL));
 
     let listener = {
-        tracing_setup(LOG_PATH, tracing::Level::DEBUG, true)?;
-        cleanup_daemon_runtime(PID_PATH, SOCK_ADDR).await?;
-        write(PID_PATH, std::process::id().to_string()).await?;
-        UnixListener::bind(SOCK_ADDR).unwrap()
+        ensure_runtime_dir(&*RUNTIME_DIR).await?;
+        tracing_setup(&*LOG_PATH, tracing::Level::DEBUG, true)?;
+        cleanup_daemon_runtime(&PID_PATH, &SOCK_ADDR).await?;
+        write(&*PID_PATH, std::process::id().to_string()).await?;
+        UnixListener::bind(&*SOCK_ADDR).unwrap()
     };
 
     pin!(os_signal_handler);
@@ -28,16 +139,338 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         select! {
             Ok((stream, _)) = listener.accept() => tokio::spawn(server::handle(stream)),
             _ = &mut os_signal_handler => break,
+            _ = server::SHUTDOWN.notified() => break,
         };
     }
 
     drop(listener);
 
-    cleanup_daemon_runtime(PID_PATH, SOCK_ADDR).await?;
+    cleanup_daemon_runtime(&PID_PATH, &SOCK_ADDR).await?;
+
+    Ok(())
+}
+
+/// Ask running daemon to stop and wait till its socket is removed
+async fn stop() -> Result<()> {
+    let mut client = connect().await?;
+    // Daemon might close the connection before responding
+    client.request(&Request::Shutdown).await.ok();
+
+    if !wait_for(|| !Path::new(&*SOCK_ADDR).exists()).await {
+        return Err(Error::Unexpected(
+            "Timed out waiting for daemon to stop".into(),
+        ));
+    }
+
+    println!("xbase stopped");
+    Ok(())
+}
+
+/// Print running daemon status
+async fn status(json: bool) -> Result<()> {
+    let mut client = connect().await?;
+    let status: DaemonStatus = client.request_as(&Request::Status).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+        return Ok(());
+    }
+
+    let DaemonStatus {
+        pid,
+        version,
+        uptime,
+        roots,
+        clients,
+        tasks,
+    } = status;
+    let (hours, minutes, seconds) = (uptime / 3600, uptime % 3600 / 60, uptime % 60);
+
+    println!("pid:     {pid}");
+    println!("version: {version}");
+    println!("uptime:  {hours}h {minutes}m {seconds}s");
+    println!("clients: {clients}");
+    println!("roots:   {}", roots.len());
+    for root in roots {
+        println!("  {}", root.display());
+    }
+    println!("tasks:   {}", tasks.len());
+    for task in tasks {
+        println!(
+            "  [{:?}] {} ({})",
+            task.kind,
+            task.target,
+            task.root.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Stop running daemon if any and start a new one in background
+async fn restart() -> Result<()> {
+    if Client::connect(&*SOCK_ADDR).await.is_ok() {
+        stop().await?;
+    }
+
+    spawn_daemon().await?;
+
+    println!("xbase started");
+    Ok(())
+}
+
+/// Print available devices indexed by platform
+async fn devices() -> Result<()> {
+    ensure_daemon().await?;
+    let runners: Runners = connect()
+        .await?
+        .request_as(&Request::RefreshDevices)
+        .await?;
+
+    for (platform, devices) in runners.platforms() {
+        if devices.is_empty() {
+            continue;
+        }
+        println!("{platform}:");
+        for device in devices {
+            println!("  {} ({})", device.name, device.id);
+        }
+    }
+
+    Ok(())
+}
+
+/// Register project root, optionally trigger build/run and print project messages.
+///
+/// Once operations return after the requested task finish, while register and watch operations
+/// keep printing messages till interrupted.
+async fn project(command: &str, flags: &Flags) -> Result<()> {
+    let root = match flags.positional() {
+        Some(root) => PathBuf::from(root),
+        None => std::env::current_dir()?,
+    };
+    let root = std::fs::canonicalize(root)?;
+    let watch = command == "watch" || flags.has("--watch", "-w");
+    let format = if flags.has("--json", "-j") {
+        OutputFormat::Json
+    } else {
+        OutputFormat::Human
+    };
+    let mut output = Output::new(format, flags.has("--verbose", "-v"));
+
+    ensure_daemon().await?;
+    let mut client = connect().await?;
+
+    let device = match flags.value("--device", "-d") {
+        Some(key) => {
+            let runners: Runners = client.request_as(&Request::RefreshDevices).await?;
+            Some(runners.find(&key).cloned().into_result("Device", &key)?)
+        }
+        None => None,
+    };
+
+    let settings = if command == "register" {
+        None
+    } else {
+        let Some(target) = flags.value("--target", "-t") else {
+            eprintln!("`--target` is required for `{command}`\n\n{USAGE}");
+            exit(2)
+        };
+        Some(BuildSettings {
+            target,
+            configuration: flags
+                .value("--configuration", "-c")
+                .unwrap_or_else(|| "Debug".into()),
+            scheme: flags.value("--scheme", "-s"),
+        })
+    };
+
+    let is_run = command == "run" || (command == "watch" && device.is_some());
+    let expected_task = if is_run {
+        TaskKind::Run
+    } else {
+        TaskKind::Build
+    };
+    let requested = settings.as_ref().map(|settings| settings.target.clone());
+    let request = |operation: Operation| {
+        let (root, settings) = (root.clone(), settings.clone()?);
+        Some(if is_run {
+            let device = device.clone();
+            Request::Run(RunRequest {
+                root,
+                settings,
+                device,
+                operation,
+            })
+        } else {
+            Request::Build(BuildRequest {
+                root,
+                settings,
+                operation,
+            })
+        })
+    };
+
+    let once = !watch && command != "register";
+    // Replaying finished tasks would end once commands early
+    let register = Request::Register(RegisterRequest {
+        root: root.clone(),
+        no_replay: once,
+        ..Default::default()
+    });
+    client.request(&register).await?;
+
+    let operation = if watch {
+        Operation::Watch
+    } else {
+        Operation::Once
+    };
+    // Errors of the requested job are prefixed by its key, see [`WatchSubscribers::spawn_job`]
+    let key = match request(operation) {
+        Some(request) => {
+            client.request(&request).await?;
+            match request {
+                Request::Run(request) => Some(format!("[{request}]")),
+                Request::Build(request) => Some(format!("[{request}]")),
+                _ => None,
+            }
+        }
+        None => None,
+    };
+    // Tasks of the requested target started after the request, by task id
+    let mut tasks = HashMap::new();
+
+    let ctrl_c = tokio::signal::ctrl_c();
+    pin!(ctrl_c);
+
+    loop {
+        let notification = select! {
+            _ = &mut ctrl_c => {
+                if let Some(stop) = request(Operation::Stop).filter(|_| watch) {
+                    client.request(&stop).await.ok();
+                }
+                return Ok(());
+            }
+            notification = client.next_notification() => match notification? {
+                Some(notification) => notification,
+                None => return Err(Error::Unexpected("Connection closed by daemon".into())),
+            },
+        };
+
+        output.print(&notification.message);
+
+        if !once {
+            continue;
+        }
 
+        // Messages of other clients or watch jobs of the same root are only printed
+        match (&notification.message, notification.task) {
+            (Message::Notify { content, level }, _)
+                if *level == ContentLevel::Error
+                    && key.as_ref().map_or(false, |key| content.starts_with(key)) =>
+            {
+                return Err(Error::Unexpected(content.clone()))
+            }
+            (Message::SetCurrentTask { kind, target, .. }, Some(task))
+                if Some(target) == requested.as_ref() =>
+            {
+                tasks.insert(task, kind.clone());
+            }
+            (Message::FinishCurrentTask { status }, Some(task)) => {
+                let target = requested.as_deref().unwrap_or_default();
+                match tasks.remove(&task) {
+                    Some(TaskKind::Build) if *status == TaskStatus::Failed => {
+                        return Err(Error::Build(format!("{target} build failed")))
+                    }
+                    Some(TaskKind::Run) if *status == TaskStatus::Failed => {
+                        return Err(Error::Run(format!("{target} exited with failure")))
+                    }
+                    Some(kind) if kind == expected_task => return Ok(()),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Start daemon in background if it isn't already running
+async fn ensure_daemon() -> Result<()> {
+    if Client::connect(&*SOCK_ADDR).await.is_err() {
+        spawn_daemon().await?;
+    }
     Ok(())
 }
 
+/// Start daemon in background and wait for its socket
+async fn spawn_daemon() -> Result<()> {
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    command
+        .arg("serve")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    if !wait_for(|| Path::new(&*SOCK_ADDR).exists()).await {
+        return Err(Error::Unexpected(
+            "Timed out waiting for daemon to start".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Print daemon logs and optionally keep printing new lines
+async fn logs(follow: bool) -> Result<()> {
+    let mut file = tokio::fs::File::open(&*LOG_PATH).await?;
+    let mut content = String::default();
+    file.read_to_string(&mut content).await?;
+    print!("{content}");
+
+    while follow {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let position = file.stream_position().await?;
+        if tokio::fs::metadata(&*LOG_PATH).await?.len() < position {
+            // Log file got replaced
+            file = tokio::fs::File::open(&*LOG_PATH).await?;
+        }
+        content.clear();
+        file.read_to_string(&mut content).await?;
+        print!("{content}");
+    }
+
+    Ok(())
+}
+
+/// Connect to running daemon
+async fn connect() -> Result<Client> {
+    let mut client = Client::connect(&*SOCK_ADDR)
+        .await
+        .map_err(|_| Error::Unexpected("xbase is not running".into()))?;
+
+    let hello = Request::Hello(HelloRequest {
+        client_name: "xbase-cli".into(),
+        protocol_version: PROTOCOL_VERSION,
+        capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+    });
+    client.request(&hello).await?;
+
+    Ok(client)
+}
+
+/// Poll condition for five seconds and return whether it got satisfied
+async fn wait_for(condition: impl Fn() -> bool) -> bool {
+    for _ in 0..50 {
+        if condition() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    false
+}
+
 /// Future that await and processes for os signals.
 async fn handle_os_signals() -> Result<()> {
     let mut signals = Signals::new(&[SIGHUP, SIGTERM, SIGINT, SIGQUIT])?;
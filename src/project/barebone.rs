@@ -15,6 +15,8 @@ pub struct BareboneProject {
     num_clients: i32,
     watchignore: Vec<String>,
     #[serde(skip)]
+    sources: TargetSources,
+    #[serde(skip)]
     xcodeproj: XCodeProject,
 }
 
@@ -42,6 +44,14 @@ impl ProjectData for BareboneProject {
     fn watchignore(&self) -> &Vec<String> {
         &self.watchignore
     }
+
+    fn target_sources(&self) -> &TargetSources {
+        &self.sources
+    }
+
+    fn target_sources_mut(&mut self) -> &mut TargetSources {
+        &mut self.sources
+    }
 }
 
 #[async_trait::async_trait]
@@ -136,7 +146,7 @@ impl Project for BareboneProject {
     async fn new(root: &PathBuf, _logger: &Arc<Broadcast>) -> Result<Self> {
         let mut project = Self {
             root: root.clone(),
-            watchignore: generate_watchignore(root).await,
+            watchignore: generate_watchignore(),
             num_clients: 1,
             ..Self::default()
         };
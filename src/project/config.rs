@@ -0,0 +1,48 @@
+use crate::watcher::{CommandConfig, WatcherConfig};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Project configuration file name, located at project root
+pub const CONFIG_FILE: &str = ".xbase.json";
+
+/// Per project daemon configuration read from [`CONFIG_FILE`]
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    pub watcher: WatcherConfig,
+    /// Commands that can be watched or ran once, see [`CommandConfig`]
+    pub commands: Vec<CommandConfig>,
+}
+
+impl ProjectConfig {
+    /// Read configuration of a given project root, defaults are used if missing or invalid
+    pub fn new(root: &Path) -> Self {
+        let path = root.join(CONFIG_FILE);
+        if !path.exists() {
+            return Self::default();
+        }
+
+        std::fs::read(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| serde_json::from_slice(&content).map_err(|err| err.to_string()))
+            .unwrap_or_else(|err| {
+                tracing::warn!("Failed to read {path:?}: {err}");
+                Self::default()
+            })
+    }
+}
+
+#[test]
+fn test_project_config_defaults() {
+    let config: ProjectConfig =
+        serde_json::from_str(r#"{ "watcher": { "backend": "poll" } }"#).unwrap();
+
+    assert_eq!(
+        config.watcher.backend,
+        Some(crate::watcher::WatchBackend::Poll)
+    );
+    assert_eq!(
+        config.watcher.poll_interval,
+        WatcherConfig::default().poll_interval
+    );
+}
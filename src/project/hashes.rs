@@ -0,0 +1,47 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Content hashes of project files, used to skip changes that don't change file content, e.g.
+/// `Package.resolved` rewritten by resolving dependencies.
+#[derive(Debug, Default)]
+pub struct ContentHashes(HashMap<PathBuf, u64>);
+
+impl ContentHashes {
+    /// Update content hash of a given path, returns true if its content changed since last update
+    pub async fn update(&mut self, path: &Path) -> bool {
+        let hash = tokio::fs::read(path).await.ok().map(|content| {
+            let mut hasher = DefaultHasher::new();
+            content.hash(&mut hasher);
+            hasher.finish()
+        });
+
+        match hash {
+            Some(hash) => self.0.insert(path.to_path_buf(), hash) != Some(hash),
+            None => self.0.remove(path).is_some(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_content_hashes() {
+    let root = std::env::temp_dir().join("xbase-test-content-hashes");
+    std::fs::remove_dir_all(&root).ok();
+    std::fs::create_dir_all(&root).unwrap();
+    let path = root.join("Package.resolved");
+    let mut hashes = ContentHashes::default();
+
+    std::fs::write(&path, "{}").unwrap();
+    assert!(hashes.update(&path).await);
+    assert!(!hashes.update(&path).await);
+
+    std::fs::write(&path, r#"{ "pins": [] }"#).unwrap();
+    assert!(hashes.update(&path).await);
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(hashes.update(&path).await);
+    assert!(!hashes.update(&path).await);
+
+    std::fs::remove_dir_all(&root).ok();
+}
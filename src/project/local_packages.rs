@@ -0,0 +1,112 @@
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Get directories of local swift packages referenced from a given xcodeproj.
+///
+/// Covers `XCLocalSwiftPackageReference` relative paths as well as package folders added as file
+/// references, both resolved from the directory containing the xcodeproj.
+pub fn xcodeproj_local_packages(xcodeproj: &Path) -> Vec<PathBuf> {
+    let Some(dir) = xcodeproj.parent() else {
+        return vec![];
+    };
+    let content = std::fs::read_to_string(xcodeproj.join("project.pbxproj")).unwrap_or_default();
+
+    let paths = content.lines().flat_map(|line| {
+        let line = line.trim();
+        let value = line
+            .strip_prefix("relativePath = ")
+            .or_else(|| line.strip_prefix("path = "))?
+            .strip_suffix(';')?
+            .trim_matches('"');
+        Some(dir.join(value))
+    });
+
+    local_packages(paths)
+}
+
+/// Get directories of local swift packages from swift package manifest as dumped by
+/// `swift package dump-package`
+pub fn manifest_local_packages(root: &Path, manifest: &Map<String, Value>) -> Vec<PathBuf> {
+    let dependencies = manifest.get("dependencies").and_then(Value::as_array);
+    let paths = dependencies
+        .into_iter()
+        .flatten()
+        .flat_map(Value::as_object)
+        .flat_map(|dependency| {
+            // swift >= 5.6: { "fileSystem": [{ "path": .. }] }
+            if let Some(file_system) = dependency.get("fileSystem").and_then(Value::as_array) {
+                return file_system
+                    .first()?
+                    .get("path")?
+                    .as_str()
+                    .map(PathBuf::from);
+            }
+            // swift < 5.6: { "url": .., "requirement": { "localPackage": .. } }
+            dependency.get("requirement")?.get("localPackage")?;
+            dependency.get("url")?.as_str().map(|url| root.join(url))
+        });
+
+    local_packages(paths)
+}
+
+/// Canonicalize and dedup paths of directories containing a `Package.swift`
+fn local_packages(paths: impl Iterator<Item = PathBuf>) -> Vec<PathBuf> {
+    let mut packages = paths
+        .filter(|path| path.join("Package.swift").exists())
+        .flat_map(|path| path.canonicalize().ok())
+        .collect::<Vec<_>>();
+    packages.sort();
+    packages.dedup();
+    packages
+}
+
+#[test]
+fn test_local_packages() {
+    use std::fs::{create_dir_all, remove_dir_all, write};
+
+    let dir = std::env::temp_dir().join("xbase-test-local-packages");
+    remove_dir_all(&dir).ok();
+    let root = dir.join("App");
+    for package in ["Shared", "Shared Kit", "Legacy"] {
+        create_dir_all(dir.join(package)).unwrap();
+        write(dir.join(package).join("Package.swift"), "").unwrap();
+    }
+    create_dir_all(root.join("App.xcodeproj")).unwrap();
+    create_dir_all(root.join("Sources")).unwrap();
+    write(
+        root.join("App.xcodeproj/project.pbxproj"),
+        r#"
+        AB12 /* XCLocalSwiftPackageReference "../Shared" */ = {
+            isa = XCLocalSwiftPackageReference;
+            relativePath = ../Shared;
+        };
+        CD34 /* Shared Kit */ = {isa = PBXFileReference; lastKnownFileType = wrapper;
+            path = "../Shared Kit";
+            sourceTree = "<group>"; };
+        EF56 /* Sources */ = {
+            path = Sources;
+        };
+        "#,
+    )
+    .unwrap();
+
+    let canonical = |name: &str| dir.join(name).canonicalize().unwrap();
+    assert_eq!(
+        xcodeproj_local_packages(&root.join("App.xcodeproj")),
+        vec![canonical("Shared"), canonical("Shared Kit")]
+    );
+
+    let manifest = serde_json::json!({
+        "dependencies": [
+            { "fileSystem": [{ "identity": "shared", "path": dir.join("Shared") }] },
+            { "url": "../Legacy", "requirement": { "localPackage": null } },
+            { "sourceControl": [{ "identity": "remote" }] },
+        ]
+    });
+    assert_eq!(
+        manifest_local_packages(&root, manifest.as_object().unwrap()),
+        vec![canonical("Legacy"), canonical("Shared")]
+    );
+
+    remove_dir_all(&dir).ok();
+}
@@ -1,14 +1,26 @@
 mod barebone;
+mod config;
+mod hashes;
+mod local_packages;
+mod sources;
 mod swift;
 mod tuist;
 mod xcodegen;
 
+pub use config::*;
+pub use hashes::ContentHashes;
+pub use local_packages::*;
+pub use sources::TargetSources;
+
+use sources::xcodeproj_target_dependencies;
+
 use crate::util::PathExt;
 use crate::*;
 use anyhow::Context;
+use futures::future::BoxFuture;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::sync::Arc;
 use xclog::{XCBuildSettings, XCLogger};
 
@@ -36,6 +48,10 @@ pub trait ProjectData: std::fmt::Debug {
     }
     /// Get Ignore patterns
     fn watchignore(&self) -> &Vec<String>;
+    /// Project targets source files
+    fn target_sources(&self) -> &TargetSources;
+    /// Get mut target source files
+    fn target_sources_mut(&mut self) -> &mut TargetSources;
     /// read dir and get xcodeproj paths
     fn get_xcodeproj_paths(&self) -> Result<Vec<PathBuf>> {
         Ok(wax::walk("*.xcodeproj", &self.root())
@@ -44,22 +60,33 @@ pub trait ProjectData: std::fmt::Debug {
             .map(|entry| entry.into_path())
             .collect::<Vec<PathBuf>>())
     }
+    /// Directories of local swift packages referenced by the project
+    fn local_packages(&self) -> Vec<PathBuf> {
+        self.get_xcodeproj_paths()
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|path| xcodeproj_local_packages(path))
+            .collect()
+    }
 }
 
 #[async_trait::async_trait]
 pub trait ProjectBuild: ProjectData {
-    /// Build Project using BuildSettings and optionally a device
+    /// Build Project using BuildSettings and optionally a device, event is the file changes that
+    /// triggered the build if any
     fn build(
         &self,
         cfg: &BuildSettings,
         device: Option<&Device>,
+        event: &EventBatch,
         broadcast: &Arc<Broadcast>,
     ) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<bool>)> {
         let mut args = cfg.to_args();
         let target = &cfg.target;
         let name = self.name().to_owned();
         let xcworkspace = format!("{}.xcworkspace", &name);
-        let task = Task::new(TaskKind::Build, target, broadcast.clone());
+        let task = Task::new(TaskKind::Build, target, broadcast.clone())
+            .track_build(BuildTracker::new(cfg, device, event));
 
         args.insert(0, "build".to_string());
 
@@ -104,26 +131,39 @@ pub trait ProjectBuild: ProjectData {
     }
 }
 
+/// Future resolving the runner of a target, e.g. from build settings once the build finished
+pub type RunnerFuture = BoxFuture<'static, Result<Box<dyn Runner + Send + Sync>>>;
+
 #[async_trait::async_trait]
 pub trait ProjectRun: ProjectData + ProjectBuild {
+    /// Start building a target and get a future resolving its runner.
+    ///
+    /// Resolving the runner may take a while (e.g. `xcodebuild -showBuildSettings`), so it's
+    /// deferred to the returned future instead of blocking the caller.
     fn get_runner(
         &self,
         cfg: &BuildSettings,
         device: Option<&Device>,
+        event: &EventBatch,
         broadcast: &Arc<Broadcast>,
-    ) -> Result<(
-        Box<dyn Runner + Send + Sync>,
-        Vec<String>,
-        tokio::sync::mpsc::Receiver<bool>,
-    )> {
-        let (args, recv) = self.build(cfg, device, broadcast)?;
-
-        let info = XCBuildSettings::new_sync(self.root(), &args)?;
-
-        let runner: Box<dyn Runner + Send + Sync> = match device {
-            Some(device) => Box::new(SimulatorRunner::new(device.clone(), &info)),
-            None => Box::new(BinRunner::from_build_info(&info)),
-        };
+    ) -> Result<(RunnerFuture, Vec<String>, tokio::sync::mpsc::Receiver<bool>)> {
+        let (args, recv) = self.build(cfg, device, event, broadcast)?;
+        let root = self.root().clone();
+        let device = device.cloned();
+        let build_args = args.clone();
+
+        let runner: RunnerFuture = Box::pin(async move {
+            let info =
+                tokio::task::spawn_blocking(move || XCBuildSettings::new_sync(&root, &build_args))
+                    .await
+                    .map_err(|e| Error::JoinError(e.to_string()))??;
+
+            let runner: Box<dyn Runner + Send + Sync> = match device {
+                Some(device) => Box::new(SimulatorRunner::new(device, &info)),
+                None => Box::new(BinRunner::from_build_info(&info)),
+            };
+            Ok::<_, Error>(runner)
+        });
 
         Ok((runner, args, recv))
     }
@@ -134,6 +174,18 @@ pub trait ProjectCompile: ProjectData {
     /// Generate compile database in project root
     async fn update_compile_database(&self, broadcast: &Arc<Broadcast>) -> Result<()>;
 
+    /// Resolve targets source files, by default from compile database in project root and
+    /// target dependencies of project xcodeproj
+    async fn update_target_sources(&mut self) -> Result<()> {
+        let mut dependencies = HashMap::new();
+        for path in self.get_xcodeproj_paths()? {
+            dependencies.extend(xcodeproj_target_dependencies(&path).await);
+        }
+        let sources = TargetSources::from_compile_database(self.root(), &dependencies).await;
+        *self.target_sources_mut() = sources;
+        Ok(())
+    }
+
     /// Get compile arguments
     fn compile_arguments(&self) -> Vec<String> {
         vec![
@@ -160,6 +212,35 @@ pub trait ProjectGenerate: ProjectData {
     }
     /// Generate xcodeproj
     async fn generate(&mut self, broadcast: &Arc<Broadcast>) -> Result<()>;
+    /// Files defining project dependencies
+    fn dependency_files(&self) -> Vec<PathBuf> {
+        vec![]
+    }
+    /// Content hashes of dependency files as of last resolve
+    fn dependency_hashes_mut(&mut self) -> Option<&mut ContentHashes> {
+        None
+    }
+    /// Whether project dependencies should be resolved before generating the project
+    fn should_resolve(&self, event: &Event) -> bool {
+        !event.is_remove_event() && self.dependency_files().contains(event.path())
+    }
+    /// Resolve project dependencies
+    async fn resolve(&mut self, _broadcast: &Arc<Broadcast>) -> Result<()> {
+        Ok(())
+    }
+    /// Update content hashes of dependency files, returns changed files
+    async fn update_dependency_hashes(&mut self, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        let Some(hashes) = self.dependency_hashes_mut() else {
+            return paths;
+        };
+        let mut changed = vec![];
+        for path in paths {
+            if hashes.update(&path).await {
+                changed.push(path);
+            }
+        }
+        changed
+    }
 }
 
 #[async_trait::async_trait]
@@ -175,7 +256,7 @@ pub trait Project:
     #[tracing::instrument(parent = None, name = "Runtime", skip_all, fields(name = self.name()))]
     async fn ensure_setup(
         &mut self,
-        event: Option<&Event>,
+        batch: Option<&EventBatch>,
         broadcast: &Arc<Broadcast>,
     ) -> Result<bool> {
         use tokio::fs::File;
@@ -227,8 +308,28 @@ pub trait Project:
             }
         };
 
-        if let Some(event) = event {
-            if self.should_generate(event) {
+        if let Some(batch) = batch {
+            // Dependency files rewritten by resolve itself have unchanged content
+            let paths = batch
+                .iter()
+                .filter(|event| self.should_resolve(event))
+                .map(|event| event.path().clone())
+                .collect();
+            let changed = self.update_dependency_hashes(paths).await;
+            let is_unchanged =
+                |event: &&Event| self.should_resolve(event) && !changed.contains(event.path());
+            let should_resolve = !changed.is_empty();
+            let should_generate = batch
+                .iter()
+                .filter(|event| !is_unchanged(event))
+                .any(|event| self.should_generate(event));
+            if should_resolve || should_generate {
+                if should_resolve {
+                    self.resolve(broadcast).await.map_err(|err| {
+                        Error::Setup(self.name().to_string(), format!("Resolve failure {err}"))
+                    })?;
+                    self.update_dependency_hashes(self.dependency_files()).await;
+                }
                 self.generate(broadcast).await.map_err(|err| {
                     Error::Setup(self.name().to_string(), format!("Generation failure {err}"))
                 })?;
@@ -237,6 +338,35 @@ pub trait Project:
                     .map_err(|err| {
                         Error::Setup(self.name().to_string(), format!("Compile database: {err}"))
                     })?;
+                self.update_target_sources().await?;
+                broadcast.reload_lsp_server();
+                return Ok(true);
+            }
+
+            // Local packages outside project root had their manifest or target sources changed
+            let is_local_package_change = |event: &Event| {
+                let from = match event.kind() {
+                    EventKind::Renamed { from, .. } => Some(from),
+                    _ => None,
+                };
+                let is_source_change = || {
+                    std::iter::once(event.path())
+                        .chain(from)
+                        .any(|path| self.target_sources().is_source_change(path))
+                };
+                let is_tree_change =
+                    event.is_create_event() || event.is_remove_event() || event.is_rename_event();
+                !event.path().starts_with(self.root())
+                    && (event.file_name() == "Package.swift"
+                        || is_tree_change && is_source_change())
+            };
+            if batch.iter().any(is_local_package_change) {
+                self.update_compile_database(broadcast)
+                    .await
+                    .map_err(|err| {
+                        Error::Setup(self.name().to_string(), format!("Compile database: {err}"))
+                    })?;
+                self.update_target_sources().await?;
                 broadcast.reload_lsp_server();
                 return Ok(true);
             }
@@ -244,6 +374,7 @@ pub trait Project:
 
         if !is_swift_project && !compile_path.exists() {
             self.update_compile_database(broadcast).await.unwrap();
+            self.update_target_sources().await?;
             broadcast.reload_lsp_server();
             Ok(true)
         } else {
@@ -258,7 +389,7 @@ pub type ProjectImpl = Box<dyn Project + Send + Sync>;
 /// Create a project from given client
 
 pub async fn project(root: &PathBuf, broadcast: &Arc<Broadcast>) -> Result<ProjectImpl> {
-    Ok(if root.join("project.yml").exists() {
+    let mut project: ProjectImpl = if root.join("project.yml").exists() {
         Box::new(xcodegen::XCodeGenProject::new(root, broadcast).await?)
     } else if root.join("Package.swift").exists() {
         Box::new(swift::SwiftProject::new(root, broadcast).await?)
@@ -266,28 +397,160 @@ pub async fn project(root: &PathBuf, broadcast: &Arc<Broadcast>) -> Result<Proje
         Box::new(tuist::TuistProject::new(root, broadcast).await?)
     } else {
         Box::new(barebone::BareboneProject::new(root, broadcast).await?)
-    })
+    };
+    project.update_target_sources().await?;
+    Ok(project)
 }
 
-async fn generate_watchignore<P: AsRef<Path>>(root: P) -> Vec<String> {
-    let mut default = vec![
+/// Default glob patterns to ignore, gitignore files are matched by the watcher, see [`IgnoreFiles`]
+fn generate_watchignore() -> Vec<String> {
+    vec![
         "**/.git/**".into(),
         "**/.*".into(),
+        format!("**/{PROBE_FILE}"),
         "**/.compile".into(),
         "**/build/**".into(),
         "**/.build/**".into(),
         "**/buildServer.json".into(),
         "**/DerivedData/**".into(),
         "**/Derived/**".into(),
-    ];
+    ]
+}
 
-    default.extend(
-        fs::gitignore_to_glob_patterns(root)
-            .await
-            .unwrap_or_default(),
-    );
+#[tokio::test]
+async fn test_ensure_setup_resolves_before_generate() {
+    use crate::util::fs::TempRoot;
+    use notify::event::{CreateKind, DataChange, EventKind, ModifyKind};
+    use std::fs::write;
+
+    #[derive(Debug, Default)]
+    struct MockProject {
+        root: PathBuf,
+        targets: HashMap<String, TargetInfo>,
+        num_clients: i32,
+        watchignore: Vec<String>,
+        sources: TargetSources,
+        dependency_hashes: ContentHashes,
+        calls: Vec<&'static str>,
+    }
+
+    impl ProjectData for MockProject {
+        fn root(&self) -> &PathBuf {
+            &self.root
+        }
+        fn name(&self) -> &str {
+            "Mock"
+        }
+        fn targets(&self) -> &HashMap<String, TargetInfo> {
+            &self.targets
+        }
+        fn clients(&self) -> &i32 {
+            &self.num_clients
+        }
+        fn clients_mut(&mut self) -> &mut i32 {
+            &mut self.num_clients
+        }
+        fn watchignore(&self) -> &Vec<String> {
+            &self.watchignore
+        }
+        fn target_sources(&self) -> &TargetSources {
+            &self.sources
+        }
+        fn target_sources_mut(&mut self) -> &mut TargetSources {
+            &mut self.sources
+        }
+    }
 
-    default.dedup();
+    #[async_trait::async_trait]
+    impl ProjectBuild for MockProject {}
 
-    default
+    #[async_trait::async_trait]
+    impl ProjectRun for MockProject {}
+
+    #[async_trait::async_trait]
+    impl ProjectCompile for MockProject {
+        async fn update_compile_database(&self, _broadcast: &Arc<Broadcast>) -> Result<()> {
+            Ok(())
+        }
+        async fn update_target_sources(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ProjectGenerate for MockProject {
+        fn should_generate(&self, event: &Event) -> bool {
+            event.file_name() == "Package.swift"
+        }
+        async fn generate(&mut self, _broadcast: &Arc<Broadcast>) -> Result<()> {
+            self.calls.push("generate");
+            Ok(())
+        }
+        fn dependency_files(&self) -> Vec<PathBuf> {
+            vec![
+                self.root.join("Package.swift"),
+                self.root.join("Package.resolved"),
+            ]
+        }
+        fn dependency_hashes_mut(&mut self) -> Option<&mut ContentHashes> {
+            Some(&mut self.dependency_hashes)
+        }
+        async fn resolve(&mut self, _broadcast: &Arc<Broadcast>) -> Result<()> {
+            self.calls.push("resolve");
+            write(
+                self.root.join("Package.resolved"),
+                self.calls.len().to_string(),
+            )?;
+            Ok(())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Project for MockProject {
+        async fn new(root: &PathBuf, _broadcast: &Arc<Broadcast>) -> Result<Self> {
+            Ok(Self {
+                root: root.clone(),
+                ..Default::default()
+            })
+        }
+    }
+
+    let root = TempRoot::new("xbase-test-ensure-setup");
+    write(root.join("Package.swift"), "// v1").unwrap();
+
+    let broadcast = Arc::new(Broadcast::new(&root));
+    let mut project = MockProject::new(&root, &broadcast).await.unwrap();
+    let batch = |kind: EventKind, file: &str| EventBatch::from_paths(&root, kind, &[file]);
+    let modified = EventKind::Modify(ModifyKind::Data(DataChange::Any));
+
+    let changed = batch(modified.clone(), "Package.swift");
+    assert!(project
+        .ensure_setup(Some(&changed), &broadcast)
+        .await
+        .unwrap());
+    assert_eq!(project.calls, vec!["resolve", "generate"]);
+
+    // Package.resolved written by resolve and Package.swift saved without changes
+    let created = batch(EventKind::Create(CreateKind::File), "Package.resolved");
+    assert!(!project
+        .ensure_setup(Some(&created), &broadcast)
+        .await
+        .unwrap());
+    let unchanged = batch(modified.clone(), "Package.swift");
+    assert!(!project
+        .ensure_setup(Some(&unchanged), &broadcast)
+        .await
+        .unwrap());
+    assert_eq!(project.calls.len(), 2);
+
+    write(root.join("Package.swift"), "// v2").unwrap();
+    let changed = batch(modified, "Package.swift");
+    assert!(project
+        .ensure_setup(Some(&changed), &broadcast)
+        .await
+        .unwrap());
+    assert_eq!(
+        project.calls,
+        vec!["resolve", "generate", "resolve", "generate"]
+    );
 }
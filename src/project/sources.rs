@@ -0,0 +1,453 @@
+use crate::watcher::{EventBatch, EventKind};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Source files and directories of project targets, used to scope watch triggers.
+///
+/// Xcode targets are resolved from `.compile` with dependencies from xcodeproj target
+/// dependencies, while swift package targets are resolved from `swift package dump-package` output.
+#[derive(Debug, Default)]
+pub struct TargetSources {
+    /// Target sources by module name
+    modules: HashMap<String, ModuleSources>,
+}
+
+#[derive(Debug, Default)]
+struct ModuleSources {
+    /// Source files or directories
+    paths: HashSet<PathBuf>,
+    /// Module names the module depends on
+    dependencies: HashSet<String>,
+}
+
+impl TargetSources {
+    /// Read target sources from compile database at a given project root, with dependencies of
+    /// targets by target name, see [`xcodeproj_target_dependencies`]
+    pub async fn from_compile_database(
+        root: &Path,
+        dependencies: &HashMap<String, HashSet<String>>,
+    ) -> Self {
+        let path = root.join(".compile");
+        let content = tokio::fs::read(&path).await;
+        let commands = match content.map(|c| serde_json::from_slice::<Value>(&c)) {
+            Ok(Ok(Value::Array(commands))) => commands,
+            Ok(Err(err)) => {
+                tracing::warn!("Failed to parse {path:?}: {err}");
+                return Self::default();
+            }
+            _ => return Self::default(),
+        };
+
+        let mut modules: HashMap<String, ModuleSources> = HashMap::default();
+        for command in commands.iter().flat_map(Value::as_object) {
+            let Some(name) = module_name(command) else {
+                continue;
+            };
+            let files = command_files(command).await;
+            modules.entry(name).or_default().paths.extend(files);
+        }
+
+        let mut sources = Self { modules };
+        for (target, dependencies) in dependencies.iter() {
+            let dependencies = dependencies
+                .iter()
+                .flat_map(|dependency| sources.module_name(dependency))
+                .map(ToString::to_string)
+                .collect::<HashSet<_>>();
+            if let Some(name) = sources.module_name(target).map(ToString::to_string) {
+                let module = sources.modules.get_mut(&name).unwrap();
+                module.dependencies.extend(dependencies);
+            }
+        }
+
+        sources
+    }
+
+    /// Get target sources from swift package manifest as dumped by `swift package dump-package`
+    pub fn from_package_manifest(root: &Path, manifest: &Map<String, Value>) -> Self {
+        let targets = manifest.get("targets").and_then(Value::as_array);
+        let modules = targets
+            .into_iter()
+            .flatten()
+            .flat_map(Value::as_object)
+            .flat_map(|target| {
+                let name = target.get("name")?.as_str()?.to_string();
+                let is_test = target.get("type").and_then(Value::as_str) == Some("test");
+                let dir = match target.get("path").and_then(Value::as_str) {
+                    Some(path) => root.join(path),
+                    None => default_package_target_dir(root, &name, is_test),
+                };
+
+                let paths = match target.get("sources").and_then(Value::as_array) {
+                    Some(sources) => sources
+                        .iter()
+                        .flat_map(Value::as_str)
+                        .map(|source| dir.join(source))
+                        .collect(),
+                    None => HashSet::from([dir]),
+                };
+
+                // byName, target and product dependencies are all encoded as [name, ..]
+                let dependencies = target
+                    .get("dependencies")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .flat_map(Value::as_object)
+                    .flat_map(|dependency| dependency.values().next()?.as_array()?.first())
+                    .flat_map(Value::as_str)
+                    .map(ToString::to_string)
+                    .collect();
+
+                Some((
+                    name,
+                    ModuleSources {
+                        paths,
+                        dependencies,
+                    },
+                ))
+            })
+            .collect();
+
+        Self { modules }
+    }
+
+    /// Whether changes within a given batch should trigger a given target.
+    ///
+    /// Changes to sources of the target or one of its dependencies trigger the target, while
+    /// changes that only belong to other targets don't. Paths unknown to any target, e.g.
+    /// resources or newly created files, as well as unresolved targets always trigger.
+    pub fn should_trigger(&self, target: &str, batch: &EventBatch) -> bool {
+        let Some(scope) = self.scope(target) else {
+            return !batch.is_empty();
+        };
+
+        batch.iter().any(|event| {
+            let from = match event.kind() {
+                EventKind::Renamed { from, .. } => Some(from),
+                _ => None,
+            };
+
+            std::iter::once(event.path())
+                .chain(from)
+                .any(|path| match self.owners(path) {
+                    owners if owners.is_empty() => true,
+                    owners => owners.iter().any(|owner| scope.contains(owner)),
+                })
+        })
+    }
+
+    /// Whether a given path changes target sources, i.e. it's a source, contains sources (e.g. a
+    /// removed folder) or sits next to sources (e.g. a file created within a source directory)
+    pub fn is_source_change(&self, path: &Path) -> bool {
+        let dir = path.parent();
+        self.modules
+            .values()
+            .flat_map(|module| module.paths.iter())
+            .any(|p| path.starts_with(p) || p.starts_with(path) || p.parent() == dir)
+    }
+
+    /// Get module name of a given target along with its transitive dependencies
+    fn scope(&self, target: &str) -> Option<HashSet<&str>> {
+        let name = self.module_name(target)?;
+
+        let mut scope = HashSet::new();
+        let mut pending = vec![name];
+        while let Some(name) = pending.pop() {
+            let Some((name, module)) = self.modules.get_key_value(name) else {
+                continue;
+            };
+            if scope.insert(name.as_str()) {
+                pending.extend(module.dependencies.iter().map(String::as_str));
+            }
+        }
+
+        Some(scope)
+    }
+
+    /// Get module name of a given target, either the target name or its c99 identifier
+    fn module_name(&self, target: &str) -> Option<&str> {
+        [target.to_string(), c99_identifier(target)]
+            .into_iter()
+            .find_map(|name| self.modules.get_key_value(&name))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Get names of modules a given path belongs to
+    fn owners(&self, path: &Path) -> Vec<&str> {
+        self.modules
+            .iter()
+            .filter(|(_, module)| module.paths.iter().any(|p| path.starts_with(p)))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+/// Get module name of a compile command from `-module-name` or `-fmodule-name` argument
+fn module_name(command: &Map<String, Value>) -> Option<String> {
+    let arguments = match command.get("arguments").and_then(Value::as_array) {
+        Some(arguments) => arguments
+            .iter()
+            .flat_map(Value::as_str)
+            .map(ToString::to_string)
+            .collect(),
+        None => shell_words::split(command.get("command")?.as_str()?).ok()?,
+    };
+
+    let mut arguments = arguments.into_iter();
+    while let Some(argument) = arguments.next() {
+        if argument == "-module-name" {
+            return arguments.next();
+        } else if let Some(name) = argument.strip_prefix("-fmodule-name=") {
+            return Some(name.to_string());
+        }
+    }
+
+    None
+}
+
+/// Get source files of a compile command, reading file lists if any
+async fn command_files(command: &Map<String, Value>) -> Vec<PathBuf> {
+    let strings = |key: &str| -> Vec<PathBuf> {
+        match command.get(key) {
+            Some(Value::String(path)) => vec![path.into()],
+            Some(Value::Array(paths)) => paths
+                .iter()
+                .flat_map(Value::as_str)
+                .map(Into::into)
+                .collect(),
+            _ => vec![],
+        }
+    };
+
+    let mut files = strings("file");
+    files.extend(strings("files"));
+    for list in strings("fileLists") {
+        let content = tokio::fs::read_to_string(list).await.unwrap_or_default();
+        files.extend(content.lines().map(PathBuf::from));
+    }
+
+    files
+}
+
+/// Get dependencies of targets in a given xcodeproj by target name.
+///
+/// Resolved from `PBXTargetDependency` objects referenced by `dependencies` of project targets.
+pub async fn xcodeproj_target_dependencies(xcodeproj: &Path) -> HashMap<String, HashSet<String>> {
+    let path = xcodeproj.join("project.pbxproj");
+    let content = tokio::fs::read_to_string(&path).await.unwrap_or_default();
+
+    #[derive(Default)]
+    struct Object<'a> {
+        isa: &'a str,
+        name: Option<&'a str>,
+        target: Option<&'a str>,
+        dependencies: Vec<&'a str>,
+    }
+
+    // Objects are multiline entries at depth 2, i.e. `{ objects = { ID = { .. }; }; }`
+    let mut objects: HashMap<&str, Object> = HashMap::new();
+    let (mut depth, mut current, mut in_dependencies) = (0, None, false);
+    for line in content.lines().map(str::trim) {
+        let level = depth;
+        depth += line.matches('{').count();
+        depth -= line.matches('}').count().min(depth);
+
+        if level == 2 && depth == 3 {
+            current = line.split_whitespace().next();
+            if let Some(id) = current {
+                objects.insert(id, Object::default());
+            }
+            continue;
+        }
+        let Some(object) = current
+            .filter(|_| level == 3)
+            .and_then(|id| objects.get_mut(id))
+        else {
+            continue;
+        };
+
+        if in_dependencies {
+            match line.split_whitespace().next() {
+                Some(")" | ");") | None => in_dependencies = false,
+                Some(id) => object.dependencies.push(id.trim_end_matches(',')),
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.trim_end_matches(';').split_once(" = ") else {
+            continue;
+        };
+        let value = value
+            .split(" /*")
+            .next()
+            .unwrap_or_default()
+            .trim_matches('"');
+        match key {
+            "isa" => object.isa = value,
+            "name" => object.name = Some(value),
+            "target" => object.target = Some(value),
+            "dependencies" => in_dependencies = value == "(",
+            _ => {}
+        }
+    }
+
+    let target_name = |id: &str| {
+        let target = objects.get(id)?.target?;
+        objects.get(target)?.name.map(ToString::to_string)
+    };
+
+    objects
+        .values()
+        .filter(|object| object.isa.ends_with("Target"))
+        .flat_map(|object| {
+            let name = object.name?.to_string();
+            let dependencies = object
+                .dependencies
+                .iter()
+                .flat_map(|id| target_name(id))
+                .collect();
+            Some((name, dependencies))
+        })
+        .collect()
+}
+
+/// Default directory of a swift package target
+fn default_package_target_dir(root: &Path, name: &str, is_test: bool) -> PathBuf {
+    let dirs: &[&str] = if is_test {
+        &["Tests"]
+    } else {
+        &["Sources", "Source", "src", "srcs"]
+    };
+
+    dirs.iter()
+        .map(|dir| root.join(dir).join(name))
+        .find(|dir| dir.exists())
+        .unwrap_or_else(|| root.join(dirs[0]).join(name))
+}
+
+/// Default product module name of an Xcode target, non alphanumeric characters replaced by `_`
+fn c99_identifier(target: &str) -> String {
+    target
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_target_sources_scope() {
+    use crate::util::fs::TempRoot;
+    use notify::event::{CreateKind, EventKind as NotifyEventKind};
+    use std::fs::{create_dir_all, write};
+
+    let root = TempRoot::new("xbase-test-target-sources");
+    create_dir_all(root.join("AppA")).unwrap();
+    create_dir_all(root.join("AppB")).unwrap();
+    create_dir_all(root.join("Core")).unwrap();
+    write(root.join("AppA/App.swift"), "import SwiftUI\nimport Core\n").unwrap();
+    write(root.join("AppB/App.swift"), "@testable import Core\n").unwrap();
+    write(root.join("Core/Core.swift"), "import Foundation\n").unwrap();
+
+    let command = |module: &str, file: &str| {
+        serde_json::json!({
+            "command": format!("swiftc -module-name {module} -c {file}"),
+            "files": [root.join(file)],
+        })
+    };
+    let commands = vec![
+        command("AppA", "AppA/App.swift"),
+        command("App_B", "AppB/App.swift"),
+        command("Core", "Core/Core.swift"),
+    ];
+    write(
+        root.join(".compile"),
+        serde_json::to_vec(&commands).unwrap(),
+    )
+    .unwrap();
+
+    let dependencies = HashMap::from([
+        ("AppA".to_string(), HashSet::from(["Core".to_string()])),
+        ("App-B".to_string(), HashSet::from(["Core".to_string()])),
+    ]);
+    let sources = TargetSources::from_compile_database(&root, &dependencies).await;
+    let kind = NotifyEventKind::Create(CreateKind::File);
+    let batch = |path: &str| EventBatch::from_paths(&root, kind.clone(), &[path]);
+
+    assert!(sources.should_trigger("AppA", &batch("AppA/App.swift")));
+    assert!(!sources.should_trigger("AppA", &batch("AppB/App.swift")));
+    assert!(sources.should_trigger("AppA", &batch("Core/Core.swift")));
+    assert!(sources.should_trigger("App-B", &batch("Core/Core.swift")));
+    assert!(!sources.should_trigger("Core", &batch("AppA/App.swift")));
+    assert!(sources.should_trigger("Core", &batch("Resources/Info.plist")));
+    assert!(sources.should_trigger("Unknown", &batch("AppA/App.swift")));
+
+    assert!(sources.is_source_change(&root.join("Core/Core.swift")));
+    assert!(sources.is_source_change(&root.join("Core/Created.swift")));
+    assert!(sources.is_source_change(&root.join("Core")));
+    assert!(!sources.is_source_change(&root.join("Docs/Guide.md")));
+    assert!(!sources.is_source_change(&root.join("README.md")));
+
+    let manifest = serde_json::json!({
+        "name": "Package",
+        "targets": [
+            { "name": "CLI", "type": "executable", "dependencies": [{ "byName": ["Core", null] }] },
+            { "name": "Core", "type": "regular", "path": "Core", "dependencies": [] },
+            { "name": "CoreTests", "type": "test", "dependencies": [{ "target": ["Core", null] }] },
+        ]
+    });
+    let sources = TargetSources::from_package_manifest(&root, manifest.as_object().unwrap());
+
+    assert!(sources.should_trigger("CLI", &batch("Sources/CLI/main.swift")));
+    assert!(sources.should_trigger("CLI", &batch("Core/Core.swift")));
+    assert!(!sources.should_trigger("CLI", &batch("Tests/CoreTests/CoreTests.swift")));
+}
+
+#[tokio::test]
+async fn test_xcodeproj_target_dependencies() {
+    use crate::util::fs::TempRoot;
+    use std::fs::{create_dir_all, write};
+
+    let root = TempRoot::new("xbase-test-target-dependencies");
+    create_dir_all(root.join("App.xcodeproj")).unwrap();
+    write(
+        root.join("App.xcodeproj/project.pbxproj"),
+        r#"// !$*UTF8*$!
+{
+	objects = {
+		A1 /* App.swift in Sources */ = {isa = PBXBuildFile; fileRef = F1 /* App.swift */; };
+		D1 /* PBXTargetDependency */ = {
+			isa = PBXTargetDependency;
+			target = T2 /* Core */;
+			targetProxy = P1 /* PBXContainerItemProxy */;
+		};
+		T1 /* My App */ = {
+			isa = PBXNativeTarget;
+			buildPhases = (
+				S1 /* Sources */,
+			);
+			buildSettings = {
+				PRODUCT_NAME = "${TARGET_NAME}";
+			};
+			dependencies = (
+				D1 /* PBXTargetDependency */,
+			);
+			name = "My App";
+		};
+		T2 /* Core */ = {
+			isa = PBXNativeTarget;
+			dependencies = (
+			);
+			name = Core;
+		};
+	};
+}
+"#,
+    )
+    .unwrap();
+
+    let dependencies = xcodeproj_target_dependencies(&root.join("App.xcodeproj")).await;
+    assert_eq!(dependencies.len(), 2);
+    assert_eq!(dependencies["My App"], HashSet::from(["Core".to_string()]));
+    assert!(dependencies["Core"].is_empty());
+}
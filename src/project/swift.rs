@@ -1,5 +1,6 @@
 use super::*;
-use crate::watcher::Event;
+use crate::util::fs::which;
+use crate::watcher::{Event, EventBatch};
 use crate::{Error, Result};
 use process_stream::Process;
 use serde::Serialize;
@@ -15,6 +16,12 @@ pub struct SwiftProject {
     targets: HashMap<String, TargetInfo>,
     num_clients: i32,
     watchignore: Vec<String>,
+    #[serde(skip)]
+    sources: TargetSources,
+    #[serde(skip)]
+    local_packages: Vec<PathBuf>,
+    #[serde(skip)]
+    dependency_hashes: ContentHashes,
 }
 
 impl ProjectData for SwiftProject {
@@ -41,6 +48,18 @@ impl ProjectData for SwiftProject {
     fn watchignore(&self) -> &Vec<String> {
         &self.watchignore
     }
+
+    fn target_sources(&self) -> &TargetSources {
+        &self.sources
+    }
+
+    fn target_sources_mut(&mut self) -> &mut TargetSources {
+        &mut self.sources
+    }
+
+    fn local_packages(&self) -> Vec<PathBuf> {
+        self.local_packages.clone()
+    }
 }
 
 #[async_trait::async_trait]
@@ -49,14 +68,16 @@ impl ProjectBuild for SwiftProject {
         &self,
         cfg: &BuildSettings,
         _device: Option<&Device>,
+        event: &EventBatch,
         broadcast: &Arc<Broadcast>,
     ) -> Result<(Vec<String>, tokio::sync::mpsc::Receiver<bool>)> {
         let args = vec!["build", "--target", &cfg.target];
-        let mut process = Process::new("/usr/bin/swift");
+        let mut process = Process::new(which("swift")?);
 
         process.args(&args);
         process.current_dir(self.root());
-        let task = Task::new(TaskKind::Build, cfg.target.as_str(), broadcast.clone());
+        let task = Task::new(TaskKind::Build, cfg.target.as_str(), broadcast.clone())
+            .track_build(BuildTracker::new(cfg, None, event));
         let recv = task.consume(Box::new(process))?;
 
         Ok((vec![], recv))
@@ -69,34 +90,40 @@ impl ProjectRun for SwiftProject {
         &self,
         cfg: &BuildSettings,
         _device: Option<&Device>,
+        event: &EventBatch,
         broadcast: &Arc<Broadcast>,
-    ) -> Result<(
-        Box<dyn Runner + Send + Sync>,
-        Vec<String>,
-        tokio::sync::mpsc::Receiver<bool>,
-    )> {
-        let (args, recv) = self.build(cfg, None, broadcast)?;
-
-        let output = std::process::Command::new("/usr/bin/swift")
-            .args(["build", "--show-bin-path"])
-            .current_dir(self.root())
-            .output()?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8(output.stderr).unwrap();
-            broadcast.open_logger();
-            return Err(Error::Run(format!(
-                "Getting target bin path failed {stderr}"
-            )));
-        }
-
-        // WARN: THIS MIGHT FAIL BECAUSE BUILD IS NOT YET RAN
-        let output = String::from_utf8(output.stdout).unwrap();
-        let bin_path = PathBuf::from(output.trim()).join(&cfg.target);
-
-        tracing::info!("Running {:?} via {bin_path:?}", self.name());
-
-        Ok((Box::new(BinRunner::from_path(&bin_path)), args, recv))
+    ) -> Result<(RunnerFuture, Vec<String>, tokio::sync::mpsc::Receiver<bool>)> {
+        let (args, recv) = self.build(cfg, None, event, broadcast)?;
+        let swift = which("swift")?;
+        let root = self.root().clone();
+        let target = cfg.target.clone();
+        let broadcast = broadcast.clone();
+
+        let runner: RunnerFuture = Box::pin(async move {
+            let output = Command::new(swift)
+                .args(["build", "--show-bin-path"])
+                .current_dir(&root)
+                .output()
+                .await?;
+
+            if !output.status.success() {
+                let stderr = String::from_utf8(output.stderr).unwrap();
+                broadcast.open_logger();
+                return Err(Error::Run(format!(
+                    "Getting target bin path failed {stderr}"
+                )));
+            }
+
+            let output = String::from_utf8(output.stdout).unwrap();
+            let bin_path = PathBuf::from(output.trim()).join(&target);
+
+            tracing::info!("Running {target:?} via {bin_path:?}");
+
+            let runner: Box<dyn Runner + Send + Sync> = Box::new(BinRunner::from_path(&bin_path));
+            Ok(runner)
+        });
+
+        Ok((runner, args, recv))
     }
 }
 
@@ -106,6 +133,11 @@ impl ProjectCompile for SwiftProject {
         // No Compile database needed for swif projects
         Ok(())
     }
+
+    async fn update_target_sources(&mut self) -> Result<()> {
+        // Resolved from package manifest, see update_project_info
+        Ok(())
+    }
 }
 #[async_trait::async_trait]
 impl ProjectGenerate for SwiftProject {
@@ -120,9 +152,40 @@ impl ProjectGenerate for SwiftProject {
             || event.is_rename_event()
     }
 
+    fn dependency_files(&self) -> Vec<PathBuf> {
+        vec![
+            self.root().join("Package.swift"),
+            self.root().join("Package.resolved"),
+        ]
+    }
+
+    fn dependency_hashes_mut(&mut self) -> Option<&mut ContentHashes> {
+        Some(&mut self.dependency_hashes)
+    }
+
+    /// Resolve package dependencies
+    async fn resolve(&mut self, broadcast: &Arc<Broadcast>) -> Result<()> {
+        let mut process: Process = vec![which("swift")?.as_str(), "package", "resolve"].into();
+        let name = self.root().name().unwrap();
+        process.current_dir(self.root());
+
+        let task = Task::new(TaskKind::Resolve, &name, broadcast.clone());
+        let success = task
+            .consume(Box::new(process))?
+            .recv()
+            .await
+            .unwrap_or_default();
+
+        if !success {
+            return Err(Error::Resolve);
+        }
+
+        Ok(())
+    }
+
     /// Generate xcodeproj
     async fn generate(&mut self, broadcast: &Arc<Broadcast>) -> Result<()> {
-        let mut process: Process = vec!["/usr/bin/swift", "build"].into();
+        let mut process: Process = vec![which("swift")?.as_str(), "build"].into();
         let name = self.root().name().unwrap();
         process.current_dir(self.root());
 
@@ -148,7 +211,7 @@ impl ProjectGenerate for SwiftProject {
 #[async_trait::async_trait]
 impl Project for SwiftProject {
     async fn new(root: &PathBuf, broadcast: &Arc<Broadcast>) -> Result<Self> {
-        let watchignore = generate_watchignore(root).await;
+        let watchignore = generate_watchignore();
 
         let mut project = Self {
             root: root.clone(),
@@ -180,7 +243,7 @@ impl SwiftProject {
         use anyhow::anyhow;
         use serde_json::{Map, Value};
 
-        let output = Command::new("/usr/bin/swift")
+        let output = Command::new(which("swift")?)
             .args(["package", "dump-package"])
             .current_dir(self.root())
             .stdin(std::process::Stdio::null())
@@ -202,6 +265,9 @@ impl SwiftProject {
             return Err(Error::DefinitionParsing(error));
         };
 
+        self.sources = TargetSources::from_package_manifest(self.root(), &map);
+        self.local_packages = manifest_local_packages(self.root(), &map);
+
         // TODO(swift-package): only provide run service for executables
         self.name = map
             .get("name")
@@ -240,3 +306,39 @@ impl SwiftProject {
         Ok(())
     }
 }
+
+#[test]
+fn test_swift_should_resolve() {
+    use crate::util::fs::TempRoot;
+    use crate::watcher::EventBatch;
+    use notify::event::{DataChange, EventKind, ModifyKind, RemoveKind};
+    use std::fs::{create_dir_all, write};
+
+    let root = TempRoot::new("xbase-test-swift-should-resolve");
+    create_dir_all(root.join("Sources/CLI")).unwrap();
+    for file in [
+        "Package.swift",
+        "Package.resolved",
+        "Sources/CLI/Package.swift",
+    ] {
+        write(root.join(file), "").unwrap();
+    }
+
+    let project = SwiftProject {
+        root: root.to_path_buf(),
+        ..Default::default()
+    };
+    let should_resolve = |kind: EventKind, file: &str| {
+        let batch = EventBatch::from_paths(&root, kind, &[file]);
+        project.should_resolve(batch.iter().next().unwrap())
+    };
+    let modified = EventKind::Modify(ModifyKind::Data(DataChange::Any));
+
+    assert!(should_resolve(modified.clone(), "Package.swift"));
+    assert!(should_resolve(modified.clone(), "Package.resolved"));
+    assert!(!should_resolve(modified, "Sources/CLI/Package.swift"));
+    assert!(!should_resolve(
+        EventKind::Remove(RemoveKind::File),
+        "Package.resolved"
+    ));
+}
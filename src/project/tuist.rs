@@ -6,7 +6,8 @@ use futures::future::try_join_all;
 use futures::StreamExt;
 use process_stream::{Process, ProcessExt};
 use serde::Serialize;
-use std::{collections::HashMap, path::PathBuf};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use tap::Pipe;
 use xcodeproj::XCodeProject;
 
@@ -18,6 +19,8 @@ pub struct TuistProject {
     num_clients: i32,
     watchignore: Vec<String>,
     #[serde(skip)]
+    sources: TargetSources,
+    #[serde(skip)]
     xcodeproj: XCodeProject,
     #[serde(skip)]
     xcodeproj_path: PathBuf,
@@ -27,6 +30,8 @@ pub struct TuistProject {
     manifest_path: PathBuf,
     #[serde(skip)]
     manifest_files: Vec<String>,
+    #[serde(skip)]
+    dependency_hashes: ContentHashes,
 }
 
 impl ProjectData for TuistProject {
@@ -53,6 +58,14 @@ impl ProjectData for TuistProject {
     fn watchignore(&self) -> &Vec<String> {
         &self.watchignore
     }
+
+    fn target_sources(&self) -> &TargetSources {
+        &self.sources
+    }
+
+    fn target_sources_mut(&mut self) -> &mut TargetSources {
+        &mut self.sources
+    }
 }
 #[async_trait::async_trait]
 impl ProjectCompile for TuistProject {
@@ -154,6 +167,47 @@ impl ProjectGenerate for TuistProject {
             || event.is_rename_event()
     }
 
+    fn dependency_files(&self) -> Vec<PathBuf> {
+        let tuist = self.root().join("Tuist");
+        vec![
+            tuist.join("Dependencies.swift"),
+            tuist.join("Package.swift"),
+            tuist.join("Package.resolved"),
+        ]
+    }
+
+    fn dependency_hashes_mut(&mut self) -> Option<&mut ContentHashes> {
+        Some(&mut self.dependency_hashes)
+    }
+
+    /// Fetch dependencies, using `tuist install` when dependencies are defined in
+    /// `Tuist/Package.swift` and `tuist fetch` otherwise
+    async fn resolve(&mut self, broadcast: &Arc<Broadcast>) -> Result<()> {
+        let command = if self.root().join("Tuist/Package.swift").exists() {
+            "install"
+        } else {
+            "fetch"
+        };
+        let mut process = Process::new(which("tuist")?);
+        let task = Task::new(TaskKind::Resolve, self.name(), broadcast.clone());
+
+        process.args([command]);
+        process.current_dir(self.root());
+        task.debug(format!("[{}] tuist {command}", self.name()));
+
+        let success = task
+            .consume(Box::new(process))?
+            .recv()
+            .await
+            .unwrap_or_default();
+
+        if !success {
+            return Err(Error::Resolve);
+        }
+
+        Ok(())
+    }
+
     /// Generate xcodeproj
     async fn generate(&mut self, broadcast: &Arc<Broadcast>) -> Result<()> {
         let task = Task::new(TaskKind::Generate, self.name(), broadcast.clone());
@@ -237,7 +291,7 @@ impl TuistProject {
 #[async_trait::async_trait]
 impl Project for TuistProject {
     async fn new(root: &PathBuf, broadcast: &Arc<Broadcast>) -> Result<Self> {
-        let mut watchignore = generate_watchignore(root).await;
+        let mut watchignore = generate_watchignore();
 
         watchignore.extend([
             "**/*.xcodeproj/**".into(),
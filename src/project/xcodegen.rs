@@ -16,6 +16,8 @@ pub struct XCodeGenProject {
     num_clients: i32,
     watchignore: Vec<String>,
     #[serde(skip)]
+    sources: TargetSources,
+    #[serde(skip)]
     xcodeproj: xcodeproj::XCodeProject,
 }
 
@@ -43,6 +45,14 @@ impl ProjectData for XCodeGenProject {
     fn watchignore(&self) -> &Vec<String> {
         &self.watchignore
     }
+
+    fn target_sources(&self) -> &TargetSources {
+        &self.sources
+    }
+
+    fn target_sources_mut(&mut self) -> &mut TargetSources {
+        &mut self.sources
+    }
 }
 
 #[async_trait::async_trait]
@@ -144,7 +154,7 @@ impl Project for XCodeGenProject {
     #[tracing::instrument(parent = None, name = "Project", skip_all, fields(name = root.name().unwrap(), kind = "xcodegen"))]
     async fn new(root: &PathBuf, broadcast: &Arc<Broadcast>) -> Result<Self> {
         tracing::info!("Processing");
-        let mut watchignore = generate_watchignore(root).await;
+        let mut watchignore = generate_watchignore();
         watchignore.extend(["**/*.xcodeproj/**".into(), "**/*.xcworkspace/**".into()]);
 
         let mut project = Self {
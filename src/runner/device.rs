@@ -1,12 +1,17 @@
 use derive_deref_rs::Deref;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::Duration;
 use std::{collections::HashMap, hash::Hash};
 use tap::Pipe;
 use typescript_type_def::TypeDef;
 use xcodeproj::pbxproj::PBXTargetPlatform;
 
-use crate::DeviceLookup;
+#[cfg(not(target_os = "macos"))]
+use crate::simctl;
+use crate::{DeviceLookup, Error, Result};
 
 #[derive(Clone, Debug, Serialize, derive_deref_rs::Deref)]
 pub struct Device {
@@ -59,9 +64,32 @@ impl Device {
             PBXTargetPlatform::Unknown => vec![],
         }
     }
+
+    /// Get `xcrun simctl <subcommand> <udid>` command to extend with subcommand arguments
+    pub fn simctl(&self, subcommand: &str) -> Result<tokio::process::Command> {
+        let mut command = tokio::process::Command::new(xcrun()?);
+        command.args(["simctl", subcommand, &self.udid]);
+        Ok(command)
+    }
 }
 
-static DEVICES: Lazy<Devices> = Lazy::new(Default::default);
+/// Get path to `xcrun`, failing on hosts without Xcode command line tools, e.g. linux
+pub fn xcrun() -> Result<PathBuf> {
+    which::which("xcrun")
+        .map_err(|_| Error::Simulator("xcrun not found, Xcode is required for simulators".into()))
+}
+
+/// How often should available devices be re-discovered
+pub const DEVICES_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+static DEVICES: Lazy<RwLock<Devices>> = Lazy::new(|| {
+    Devices::discover()
+        .unwrap_or_else(|err| {
+            tracing::error!("{err}");
+            Devices(HashMap::default())
+        })
+        .pipe(RwLock::new)
+});
 
 /// Represntaiton of Project runners index by Platfrom
 #[derive(Debug, Serialize, Deserialize, TypeDef)]
@@ -69,7 +97,7 @@ pub struct Runners(HashMap<String, Vec<DeviceLookup>>);
 
 impl Default for Runners {
     fn default() -> Self {
-        let devices = &*DEVICES;
+        let devices = DEVICES.read().unwrap_or_else(|err| err.into_inner());
         vec![
             PBXTargetPlatform::IOS,
             PBXTargetPlatform::WatchOS,
@@ -91,25 +119,66 @@ impl Default for Runners {
     }
 }
 
-impl Default for Devices {
-    fn default() -> Self {
-        Devices(
-            simctl::Simctl::new()
-                .list()
-                .unwrap()
-                .devices()
-                .to_vec()
-                .into_iter()
-                .filter(|d| d.is_available)
-                .map(|d| (d.udid.clone(), Device::from(d)))
-                .collect(),
-        )
+impl Runners {
+    /// Find device lookup by device name or id
+    pub fn find(&self, key: &str) -> Option<&DeviceLookup> {
+        self.0
+            .values()
+            .flatten()
+            .find(|d| d.id == key || d.name == key)
+    }
+
+    /// Get device lookups indexed by platform
+    pub fn platforms(&self) -> &HashMap<String, Vec<DeviceLookup>> {
+        &self.0
     }
 }
 
 impl Devices {
+    /// Discover available devices using `simctl list`
+    pub fn discover() -> Result<Self> {
+        if xcrun().is_err() {
+            tracing::debug!("xcrun not found, skipping devices discovery");
+            return Ok(Self(HashMap::default()));
+        }
+
+        simctl::Simctl::new()
+            .list()
+            .map_err(|e| Error::DeviceDiscovery(Error::from(e).to_string()))?
+            .devices()
+            .iter()
+            .filter(|d| d.is_available)
+            .map(|d| (d.udid.clone(), Device::from(d.clone())))
+            .collect::<HashMap<_, _>>()
+            .pipe(Self)
+            .pipe(Ok)
+    }
+
+    /// Re-discover available devices and return true if devices changed since last discovery.
+    pub fn refresh() -> Result<bool> {
+        let devices = Self::discover()?;
+        let mut current = DEVICES.write().unwrap_or_else(|err| err.into_inner());
+        if current.is_same(&devices) {
+            return Ok(false);
+        }
+        *current = devices;
+        Ok(true)
+    }
+
     /// Get Device from Device lookup
     pub fn from_lookup(lookup: Option<DeviceLookup>) -> Option<Device> {
-        lookup.and_then(|d| DEVICES.get(&d.id)).cloned()
+        let devices = DEVICES.read().unwrap_or_else(|err| err.into_inner());
+        lookup.and_then(|d| devices.get(&d.id).cloned())
+    }
+
+    /// Whether both have the same devices with the same names and states
+    fn is_same(&self, other: &Devices) -> bool {
+        self.len() == other.len()
+            && self.iter().all(|(id, device)| {
+                other
+                    .get(id)
+                    .map(|d| d.name == device.name && d.state == device.state)
+                    .unwrap_or_default()
+            })
     }
 }
@@ -48,43 +48,49 @@ impl Watchable for RunService {
     async fn trigger(
         &self,
         project: &mut ProjectImpl,
-        _event: &Event,
+        event: &EventBatch,
         broadcast: &Arc<Broadcast>,
-    ) -> Result<()> {
+    ) -> Result<WatchJob> {
         let Self { settings, .. } = self;
 
-        let mut handler = self.handler.clone().lock_owned().await;
-
-        handler.take().map(|v| {
+        self.handler.lock().await.take().map(|v| {
             v.process().abort();
             v.inner().abort();
         });
 
         let device = self.device.as_ref();
-        let target = &settings.target;
-        let (runner, _args, mut recv) = project.get_runner(&settings, device, broadcast)?;
-
-        if !recv.recv().await.unwrap_or_default() {
-            return Err(crate::Error::Run(format!("{target} build failed")));
-        }
+        let target = settings.target.clone();
+        let (runner, _args, mut recv) = project.get_runner(&settings, device, event, broadcast)?;
+        let handler = self.handler.clone();
+        let broadcast = broadcast.clone();
+
+        Ok(Box::pin(async move {
+            if !recv.recv().await.unwrap_or_default() {
+                return Err(crate::Error::Run(format!("{target} build failed")));
+            }
 
-        let task = Task::new(TaskKind::Run, target, broadcast.clone());
+            let runner = runner.await?;
+            let task = Task::new(TaskKind::Run, &target, broadcast.clone());
 
-        let runner = runner.run(&task).await?;
-        let broadcast = Arc::downgrade(broadcast);
+            let runner = runner.run(&task).await?;
+            let broadcast = Arc::downgrade(&broadcast);
 
-        *handler = Some(RunHandler::new(target, runner, broadcast)?);
+            let (id, log) = (task.id(), task.log());
+            *handler.lock().await = Some(RunHandler::new(&target, runner, broadcast, id, log)?);
 
-        Ok(())
+            Ok(())
+        }))
     }
 
     /// A function that controls whether a a Watchable should restart
-    async fn should_trigger(&self, event: &Event) -> bool {
-        event.is_any_but_not_seen()
+    async fn should_trigger(&self, project: &ProjectImpl, event: &EventBatch) -> bool {
+        project
+            .target_sources()
+            .should_trigger(&self.settings.target, event)
     }
 
     /// A function that controls whether a watchable should be droped
-    async fn should_discard(&self, _event: &Event) -> bool {
+    async fn should_discard(&self, _event: &EventBatch) -> bool {
         false
     }
 
@@ -105,7 +111,13 @@ pub struct RunHandler {
 
 impl RunHandler {
     // Change the status of the process to running
-    pub fn new(target: &String, mut process: Process, broadcast: Weak<Broadcast>) -> Result<Self> {
+    pub fn new(
+        target: &String,
+        mut process: Process,
+        broadcast: Weak<Broadcast>,
+        task: usize,
+        log: Option<Arc<TaskLog>>,
+    ) -> Result<Self> {
         let target = target.clone();
         let mut stream = process.spawn_and_stream()?;
         let abort = process.aborter().unwrap();
@@ -124,25 +136,38 @@ impl RunHandler {
                     }
                 };
 
+                let write_log = |line: &str| {
+                    if let Some(log) = log.as_ref() {
+                        log.write(line)
+                    }
+                };
+
+                if let Some(log) = log.as_ref() {
+                    log.write_raw(output.to_string())
+                }
+
                 use process_stream::ProcessItem::*;
                 match output {
                     Output(msg) => {
+                        write_log(&msg);
                         if !msg.contains("ignoring singular matrix") {
                             broadcast.log_info(msg);
                         }
                     }
                     Error(msg) => {
+                        write_log(&msg);
                         broadcast.log_error(msg);
                     }
                     // TODO: this should be skipped when user re-run the app
                     Exit(code) => {
                         let success = &code == "0";
+                        write_log(if success { "[Succeeded]" } else { "[Failed]" });
                         if success {
                             broadcast.log_info("Device Disconnected");
                         } else {
                             broadcast.log_error("Device Disconnected");
                         }
-                        broadcast.finish_current_task(success);
+                        broadcast.finish_current_task(task, success);
                         tracing::info!("[{target}] Runner Closed");
                         break;
                     }
@@ -1,9 +1,10 @@
 use super::*;
+#[cfg(not(target_os = "macos"))]
+use crate::simctl;
 use crate::*;
 use process_stream::Process;
 use std::path::PathBuf;
 use tap::Pipe;
-use tokio::process::Command;
 use xclog::XCBuildSettings;
 
 /// Simulator Device runner
@@ -33,6 +34,24 @@ impl SimulatorRunner {
     }
 
     pub async fn boot<'a>(&self, task: &Task) -> Result<()> {
+        self.launch_simulator_app(task).await?;
+
+        task.info(self.booting_msg());
+        if let Err(e) = self.device.boot() {
+            let err: Error = e.into();
+            let err_msg = err.to_string();
+            if !err_msg.contains("current state Booted") {
+                // task.log_error(err_msg);
+            }
+        }
+        Ok(())
+    }
+
+    /// Launch Simulator.app if it isn't already running
+    #[cfg(target_os = "macos")]
+    async fn launch_simulator_app(&self, task: &Task) -> Result<()> {
+        use tokio::process::Command;
+
         match pid::get_pid_by_name("Simulator") {
             Err(Error::Lookup(_, _)) => {
                 task.info(format!("[Simulator] Launching"));
@@ -48,18 +67,15 @@ impl SimulatorRunner {
             }
             _ => {}
         }
-
-        task.info(self.booting_msg());
-        if let Err(e) = self.device.boot() {
-            let err: Error = e.into();
-            let err_msg = err.to_string();
-            if !err_msg.contains("current state Booted") {
-                // task.log_error(err_msg);
-            }
-        }
         Ok(())
     }
 
+    /// Simulators are only available on macOS
+    #[cfg(not(target_os = "macos"))]
+    async fn launch_simulator_app(&self, _task: &Task) -> Result<()> {
+        Err(Error::Run("Simulators are only supported on macOS".into()))
+    }
+
     pub async fn install<'a>(&self, task: &Task) -> Result<()> {
         task.info(self.installing_msg());
         self.device
@@ -71,7 +87,7 @@ impl SimulatorRunner {
 
     pub async fn launch<'a>(&self, task: &Task) -> Result<Process> {
         task.info(self.launching_msg());
-        let mut process = Process::new("xcrun");
+        let mut process = Process::new(xcrun()?);
         let args = &[
             "simctl",
             "launch",
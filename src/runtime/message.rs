@@ -1,13 +1,20 @@
-use crate::server::{BuildRequest, RunRequest};
-use crate::{Event, PathExt};
-use std::{collections::HashSet, path::PathBuf};
-use tokio::sync::mpsc;
+use crate::server::{BuildRequest, CommandRequest, RunRequest};
+use crate::{Broadcast, CommandConfig, Device, EventBatch, PathExt, PauseMode, ProjectImpl};
+use crate::{ReplayFilter, Result, WatchBackend};
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
+use tokio::sync::{mpsc, oneshot};
 
 /// Project Runime Message
 #[derive(Debug)]
 pub enum PRMessage {
-    /// Process filesystem changes
-    FSEvent(Event),
+    /// Process filesystem changes received within a debounce window
+    FSEvents(EventBatch),
+    /// Project setup of a given batch finished in background
+    SetupFinished {
+        project: ProjectImpl,
+        batch: EventBatch,
+        result: Result<bool>,
+    },
     /// Connect to client
     Connect(u32),
     /// Disconnect from client
@@ -16,16 +23,34 @@ pub enum PRMessage {
     Run(RunRequest),
     /// Process Build Request
     Build(BuildRequest),
+    /// Process Command Request
+    Command(CommandRequest),
+    /// Update clients runners state
+    RefreshRunners,
+    /// Get device of an active run, if any
+    RunDevice(oneshot::Sender<Option<Device>>),
+    /// Get configuration of a command by name
+    CommandConfig(String, oneshot::Sender<Option<CommandConfig>>),
+    /// Reload project configuration file
+    ReloadConfig,
+    /// Update active file watcher backend
+    WatchBackend(WatchBackend),
+    /// File watcher failed to start or fall back to polling
+    WatchFailed(String),
+    /// Pause processing filesystem changes
+    PauseWatch(PauseMode),
+    /// Resume processing filesystem changes
+    ResumeWatch,
 }
 
 #[derive(Debug)]
 pub struct PRMessageSender {
     /// Project Root
     root: PathBuf,
-    /// Message Broadcaster address
-    broadcaster_adderss: PathBuf,
     /// PRMessage sender
     sender: mpsc::UnboundedSender<PRMessage>,
+    /// Client Broadcaster
+    broadcaster: Arc<Broadcast>,
     /// Connect Cilents
     clients: HashSet<u32>,
 }
@@ -33,13 +58,13 @@ pub struct PRMessageSender {
 impl PRMessageSender {
     pub fn new(
         root: &PathBuf,
-        baddress: &PathBuf,
+        broadcaster: &Arc<Broadcast>,
         sender: &mpsc::UnboundedSender<PRMessage>,
     ) -> Self {
         Self {
             root: root.clone(),
-            broadcaster_adderss: baddress.clone(),
             sender: sender.clone(),
+            broadcaster: broadcaster.clone(),
             clients: HashSet::default(),
         }
     }
@@ -52,13 +77,24 @@ impl PRMessageSender {
         self.root().name().unwrap()
     }
 
-    pub fn connect(&mut self, id: u32) {
+    /// Subscribe client to project messages and tell the runtime about it.
+    ///
+    /// Messages are queued till the client is attached with [`PRMessageSender::attach`].
+    pub fn connect(&mut self, id: u32, replay: Option<ReplayFilter>) {
         if !self.clients.contains(&id) {
+            self.broadcaster.subscribe(id, replay);
             self.send(PRMessage::Connect(id));
             self.clients.insert(id);
         }
     }
 
+    /// Start writing project messages to a connected client
+    pub fn attach(&self, id: u32) {
+        if self.clients.contains(&id) {
+            self.broadcaster.attach(id);
+        }
+    }
+
     pub fn disconnect(&mut self, id: u32) {
         if self.clients.contains(&id) {
             self.clients.remove(&id);
@@ -76,10 +112,6 @@ impl PRMessageSender {
         self.sender.is_closed()
     }
 
-    pub fn broadcaster_adderss(&self) -> &PathBuf {
-        &self.broadcaster_adderss
-    }
-
     pub fn contains(&self, value: &u32) -> bool {
         self.clients.contains(value)
     }
@@ -1,5 +1,7 @@
 mod message;
+mod pause;
 pub use message::*;
+pub use pause::*;
 
 use crate::{server::*, *};
 use std::{path::PathBuf, sync::Arc};
@@ -10,8 +12,10 @@ use tracing::{info, instrument};
 pub struct ProjectRuntime {
     /// Project Name
     name: String,
-    /// Project Data
-    project: ProjectImpl,
+    /// Project Root
+    root: PathBuf,
+    /// Project Data, None while project setup is running in background
+    project: Option<ProjectImpl>,
     /// Client Broadcaster
     broadcaster: Arc<Broadcast>,
     /// Receiver to receive PRMessages,
@@ -20,12 +24,24 @@ pub struct ProjectRuntime {
     sender: mpsc::UnboundedSender<PRMessage>,
     /// Build/Run requests subscribed to changes
     watcher_subscribers: WatchSubscribers,
-    /// Build/Run requests subscribed to changes
-    watcher_state: WatcherState,
     /// Connect clients id
     clients: u32,
+    /// Devices of runs along with their run handlers, by run key
+    run_devices: HashMap<String, (Device, Arc<Mutex<Option<RunHandler>>>)>,
+    /// Project configuration
+    config: ProjectConfig,
+    /// Active file watcher backend
+    watch_backend: Option<WatchBackend>,
+    /// File changes received while watching is paused
+    pause: WatchPause,
+    /// File changes received while project setup is running
+    pending: EventBatch,
+    /// Messages requiring the project received while project setup is running
+    deferred: Vec<PRMessage>,
     /// Notifer to notify listeners that this runtime is no longer active
     abort: Arc<Notify>,
+    /// Sender of updated extra roots to the file watcher, None till initial setup is finished
+    watch_roots: Option<mpsc::UnboundedSender<Vec<PathBuf>>>,
 }
 
 impl ProjectRuntime {
@@ -33,19 +49,26 @@ impl ProjectRuntime {
     pub async fn new(root: PathBuf) -> Result<(Self, PRMessageSender)> {
         info!("[Initializing] ------------------------");
         let (sender, receiver) = mpsc::unbounded_channel::<PRMessage>();
-        let broadcaster = Arc::new(Broadcast::new(&root).await?);
+        let broadcaster = Arc::new(Broadcast::new(&root));
         let project = project::project(&root, &broadcaster).await?;
-        let rsender = PRMessageSender::new(&root, broadcaster.address(), &sender);
+        let rsender = PRMessageSender::new(&root, &broadcaster, &sender);
         let name = project.name().to_string();
         let watcher_subscribers = WatchSubscribers::new(&name);
         let runtime = Self {
             name,
             clients: Default::default(),
+            run_devices: Default::default(),
+            config: ProjectConfig::new(&root),
+            watch_backend: Default::default(),
+            pause: Default::default(),
+            pending: Default::default(),
+            deferred: Default::default(),
             abort: Default::default(),
-            watcher_state: WatcherState::new(),
+            watch_roots: Default::default(),
             watcher_subscribers,
             broadcaster,
-            project,
+            project: Some(project),
+            root,
             receiver,
             sender,
         };
@@ -54,85 +77,165 @@ impl ProjectRuntime {
 
     /// Start Runtime Loop
     #[instrument(parent = None, name = "Runtime", skip_all, fields(name = self.name))]
-    pub async fn start(mut self, id: u32) {
-        if let Err(err) = self
-            .project
-            .ensure_setup(None.as_ref(), &self.broadcaster)
-            .await
-        {
-            self.broadcaster.error(format!("[{}]  {err}", self.name));
-        };
-
-        tokio::spawn(
-            Watcher::new(
-                &self.name,
-                &self.watcher_state,
-                &self.sender,
-                &self.abort,
-                self.project.root(),
-                self.project.watchignore(),
-            )
-            .start(),
-        );
-        self.on_connect(id);
+    pub async fn start(mut self) {
+        self.setup(EventBatch::default());
 
         info!("[Initialized] -------------------------");
-        while let Some(message) = self.receiver.recv().await {
-            match message {
-                PRMessage::Connect(id) => self.on_connect(id),
-                PRMessage::Disconnect(id) => {
-                    info!("Disconnected [{id}]");
-                    self.clients -= 1;
-                    self.broadcaster.send(Some(id), Message::Disconnect);
-                    if self.clients.eq(&0) {
-                        self.broadcaster.abort();
-                        self.abort.notify_waiters();
-                        tokio::spawn(async move { runtimes().await.remove(self.project.root()) });
-                        break;
-                    }
+        'runtime: while let Some(message) = self.receiver.recv().await {
+            if !self.on_message(message).await {
+                break;
+            }
+            // Process messages deferred while project setup was running
+            while self.project.is_some() && !self.deferred.is_empty() {
+                let message = self.deferred.remove(0);
+                if !self.on_message(message).await {
+                    break 'runtime;
                 }
-                PRMessage::FSEvent(event) => self.on_fs_event(event).await,
-                PRMessage::Run(req) => self.on_run(req).await,
-                PRMessage::Build(req) => self.on_build(req).await,
             }
         }
         info!("[Dropped]");
     }
 
+    /// Process a runtime message, returns false once the runtime should be dropped
+    async fn on_message(&mut self, message: PRMessage) -> bool {
+        match message {
+            PRMessage::Connect(id) => self.on_connect(id),
+            PRMessage::Disconnect(id) => {
+                info!("Disconnected [{id}]");
+                self.clients -= 1;
+                self.broadcaster.send(Some(id), Message::Disconnect);
+                if self.clients.eq(&0) {
+                    self.broadcaster.abort();
+                    self.abort.notify_waiters();
+                    let root = self.root.clone();
+                    tokio::spawn(async move { runtimes().await.remove(&root) });
+                    return false;
+                }
+            }
+            PRMessage::FSEvents(batch) => self.on_fs_events(batch),
+            PRMessage::SetupFinished {
+                project,
+                batch,
+                result,
+            } => self.on_setup_finished(project, batch, result).await,
+            // Triggers need the project, they are processed once project setup is finished
+            message @ (PRMessage::Run(_)
+            | PRMessage::Build(_)
+            | PRMessage::Command(_)
+            | PRMessage::ResumeWatch)
+                if self.project.is_none() =>
+            {
+                self.deferred.push(message)
+            }
+            PRMessage::Run(req) => self.on_run(req).await,
+            PRMessage::Build(req) => self.on_build(req).await,
+            PRMessage::Command(req) => self.on_command(req).await,
+            PRMessage::RefreshRunners => self.set_client_runner_state(None),
+            PRMessage::RunDevice(tx) => tx.send(self.run_device().await).unwrap_or_default(),
+            PRMessage::CommandConfig(name, tx) => {
+                tx.send(self.command_config(&name)).unwrap_or_default()
+            }
+            PRMessage::ReloadConfig => self.on_reload_config(),
+            PRMessage::WatchBackend(backend) => {
+                self.watch_backend = Some(backend);
+                self.set_client_project_state(None);
+            }
+            PRMessage::WatchFailed(err) => {
+                let msg = format!("[{}] Failed to watch files: {err}", self.name);
+                self.broadcaster.error(msg);
+            }
+            PRMessage::PauseWatch(mode) => self.on_pause_watch(mode),
+            PRMessage::ResumeWatch => self.on_resume_watch(),
+        }
+        true
+    }
+
     fn on_connect(&mut self, id: u32) {
         info!("Connected [{id}]");
         self.clients += 1;
         let msg = format!("[{}] Registered", self.name);
         self.broadcaster.info_with_id(id, msg);
         self.set_client_project_state(Some(id));
-        self.set_client_runner_state(id);
+        self.set_client_runner_state(Some(id));
     }
 
     #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
-    async fn on_fs_event(&mut self, event: Event) {
-        let name = &self.name;
-
-        info!("Processing {event}");
-        if event.is_create_event()
-            || event.is_remove_event()
-            || event.is_content_update_event()
-            || event.is_rename_event() && !event.is_seen()
-        {
-            let ensure_setup = self.project.ensure_setup(Some(&event), &self.broadcaster);
-            match ensure_setup.await {
-                Err(e) => self.broadcaster.error(format!("[{name}] {e}")),
-                Ok(true) => self.set_client_project_state(None),
-                _ => {}
-            };
-        }
+    fn on_fs_events(&mut self, batch: EventBatch) {
+        let Some(batch) = self.pause.record(batch) else {
+            info!("Paused, deferring changes till watching is resumed");
+            return;
+        };
 
-        self.watcher_subscribers
-            .trigger(&mut self.project, &event, &self.broadcaster)
-            .await;
+        self.setup(batch)
+    }
+
+    /// Ensure project setup for a given batch in background, an empty batch ensures setup
+    /// regardless of changes. Watch subscribers are triggered once setup is finished.
+    fn setup(&mut self, batch: EventBatch) {
+        let Some(mut project) = self.project.take() else {
+            info!("Setup is running, queuing {batch}");
+            self.pending.append(batch);
+            return;
+        };
+
+        info!("Processing {batch}");
+        let broadcast = self.broadcaster.clone();
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let event = (!batch.is_empty()).then_some(&batch);
+            let result = project.ensure_setup(event, &broadcast).await;
+            let message = PRMessage::SetupFinished {
+                project,
+                batch,
+                result,
+            };
+            sender.send(message).ok();
+        });
+    }
 
-        info!("Processed {event}");
+    #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
+    async fn on_setup_finished(
+        &mut self,
+        project: ProjectImpl,
+        batch: EventBatch,
+        result: Result<bool>,
+    ) {
+        // Local packages are read from project manifest or xcodeproj
+        let is_manifest_change = |event: &Event| {
+            matches!(
+                event.file_name().as_str(),
+                "Package.swift" | "project.pbxproj"
+            )
+        };
+        let update_watch_roots =
+            batch.is_empty() || matches!(result, Ok(true)) || batch.iter().any(is_manifest_change);
+        if let Err(e) = result {
+            self.broadcaster.error(format!("[{}] {e}", self.name));
+        }
+        let project = self.project.insert(project);
+        if batch.is_empty() {
+            self.watcher_subscribers
+                .trigger_all(project, &self.broadcaster)
+                .await;
+        } else {
+            self.watcher_subscribers
+                .trigger(project, &batch, &self.broadcaster)
+                .await;
+        }
+        // Watcher is started once initial setup generated watchignore and read local packages
+        if self.watch_roots.is_none() {
+            self.start_watcher();
+        } else if update_watch_roots {
+            self.update_watch_roots();
+        }
+        self.set_client_project_state(None);
+        info!("Processed {batch}");
 
-        self.watcher_state.update_debounce();
+        // Changes received while setup was running are processed at once
+        let pending = std::mem::take(&mut self.pending);
+        if !pending.is_empty() {
+            self.setup(pending);
+        }
     }
 
     #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
@@ -142,14 +245,30 @@ impl ProjectRuntime {
             req.operation.is_watch()
         } else {
             self.watcher_subscribers.remove(&req).await;
+            self.run_devices.remove(&req.to_string());
             return;
         };
         let service = req.into_service();
-        let event = Event::default();
-        let res = service.trigger(&mut self.project, &event, &self.broadcaster);
-        if let Err(err) = res.await {
-            let msg = format!("[{}] failed to start runner {err}", self.name);
-            self.broadcaster.error(msg);
+        if let Some(device) = service.device.clone() {
+            let handler = service.handler.clone();
+            self.run_devices
+                .insert(service.to_string(), (device, handler));
+        }
+        let event = EventBatch::default();
+        let project = self
+            .project
+            .as_mut()
+            .expect("deferred while setup is running");
+        let res = service.trigger(project, &event, &self.broadcaster);
+        match res.await {
+            Ok(job) => self
+                .watcher_subscribers
+                .spawn_job(&service, job, &self.broadcaster),
+            // Prefixed by the request key like job errors, see [`WatchSubscribers::spawn_job`]
+            Err(err) => {
+                let msg = format!("[{service}] failed to start runner {err}");
+                self.broadcaster.error(msg);
+            }
         }
         info!("Ran {}", service.settings.target);
         if is_watch {
@@ -167,11 +286,21 @@ impl ProjectRuntime {
         };
 
         info!("Building {}", req.settings.target);
-        let event = Event::default();
-        let res = req.trigger(&mut self.project, &event, &self.broadcaster);
-        if let Err(err) = res.await {
-            let msg = format!("[{}] failed to start runner {err}", self.name);
-            self.broadcaster.error(msg);
+        let event = EventBatch::default();
+        let project = self
+            .project
+            .as_mut()
+            .expect("deferred while setup is running");
+        let res = req.trigger(project, &event, &self.broadcaster);
+        match res.await {
+            Ok(job) => self
+                .watcher_subscribers
+                .spawn_job(&req, job, &self.broadcaster),
+            // Prefixed by the request key like job errors, see [`WatchSubscribers::spawn_job`]
+            Err(err) => {
+                let msg = format!("[{req}] failed to start build {err}");
+                self.broadcaster.error(msg);
+            }
         }
         info!("Built {}", req.settings.target);
         if is_watch {
@@ -179,16 +308,171 @@ impl ProjectRuntime {
         }
     }
 
+    #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
+    async fn on_command(&mut self, req: CommandRequest) {
+        let is_watch = if !req.operation.is_stop() {
+            req.operation.is_watch()
+        } else {
+            self.watcher_subscribers.remove(&req).await;
+            return;
+        };
+
+        info!("Running {}", req.name);
+        let config = self
+            .command_config(&req.name)
+            .into_result("Command", &req.name);
+        let watchable = match config.and_then(|config| req.into_watchable(config)) {
+            Ok(watchable) => watchable,
+            Err(err) => {
+                self.broadcaster.error(format!("[{}] {err}", self.name));
+                return;
+            }
+        };
+        let event = EventBatch::default();
+        let project = self
+            .project
+            .as_mut()
+            .expect("deferred while setup is running");
+        let res = watchable.trigger(project, &event, &self.broadcaster);
+        match res.await {
+            Ok(job) => self
+                .watcher_subscribers
+                .spawn_job(&watchable, job, &self.broadcaster),
+            Err(err) => {
+                let msg = format!("[{}] failed to run command {err}", self.name);
+                self.broadcaster.error(msg);
+            }
+        }
+        if is_watch {
+            self.watcher_subscribers.add(watchable);
+        }
+    }
+
+    /// Get configuration of a command by name
+    fn command_config(&self, name: &str) -> Option<CommandConfig> {
+        let mut commands = self.config.commands.iter();
+        commands.find(|command| command.name == name).cloned()
+    }
+
+    fn on_reload_config(&mut self) {
+        info!("Reloading {CONFIG_FILE}");
+        self.config = ProjectConfig::new(&self.root);
+        self.update_watch_roots();
+        self.set_client_project_state(None);
+    }
+
+    fn on_pause_watch(&mut self, mode: PauseMode) {
+        info!("Paused watching ({mode:?})");
+        self.pause.pause(mode);
+        self.broadcaster
+            .info(format!("[{}] Watching paused", self.name));
+        self.set_client_project_state(None);
+    }
+
+    /// Resume watching and reconcile changes received while paused, if any, at once
+    fn on_resume_watch(&mut self) {
+        if self.pause.mode().is_none() {
+            return;
+        }
+        let reconcile = self.pause.resume();
+
+        info!("Resumed watching");
+        self.broadcaster
+            .info(format!("[{}] Watching resumed", self.name));
+        self.set_client_project_state(None);
+
+        match reconcile {
+            Some(Reconcile::Batch(batch)) => self.setup(batch),
+            Some(Reconcile::Full) => self.setup(EventBatch::default()),
+            None => {}
+        }
+    }
+
+    /// Get device of a run whose process is still running
+    async fn run_device(&self) -> Option<Device> {
+        for (device, handler) in self.run_devices.values() {
+            let handler = handler.lock().await;
+            if handler.as_ref().map_or(false, |h| !h.inner().is_finished()) {
+                return Some(device.clone());
+            }
+        }
+        None
+    }
+
+    /// Start file watcher of project root and extra roots, see [`Self::extra_watch_roots`]
+    fn start_watcher(&mut self) {
+        let Some(project) = self.project.as_ref() else {
+            return;
+        };
+        let (watch_roots, extra_roots_updates) = mpsc::unbounded_channel();
+        tokio::spawn(
+            Watcher::new(
+                &self.name,
+                &self.sender,
+                &self.abort,
+                project.root(),
+                project.watchignore(),
+                self.extra_watch_roots(),
+                extra_roots_updates,
+                &self.config.watcher,
+            )
+            .start(),
+        );
+        self.watch_roots = Some(watch_roots);
+    }
+
+    /// Send extra roots to the file watcher, which only acts on changed roots
+    fn update_watch_roots(&self) {
+        if let Some(watch_roots) = self.watch_roots.as_ref() {
+            watch_roots.send(self.extra_watch_roots()).ok();
+        }
+    }
+
+    /// Get local packages and configured paths outside project root
+    fn extra_watch_roots(&self) -> Vec<PathBuf> {
+        let root = &self.root;
+        let canonical_root = root.canonicalize().unwrap_or_else(|_| root.clone());
+        let configured = self.config.watcher.paths.iter().flat_map(|path| {
+            let path = root.join(path);
+            path.canonicalize()
+                .map_err(|err| tracing::warn!("Ignoring watch path {path:?}: {err}"))
+                .ok()
+        });
+
+        let mut roots = self
+            .project
+            .as_ref()
+            .map(|project| project.local_packages())
+            .unwrap_or_default();
+        roots.extend(configured);
+        roots.retain(|path| !path.starts_with(&canonical_root));
+        roots.sort();
+        roots.dedup();
+        roots
+    }
+
+    /// Set clients project state, skipped while setup is running as it's set once finished
     fn set_client_project_state(&mut self, id: Option<u32>) {
+        let Some(project) = self.project.as_ref() else {
+            return;
+        };
         let info = ProjectInfo {
             watchlist: self.watcher_subscribers.keys(),
-            targets: self.project.targets().clone(),
+            targets: project.targets().clone(),
+            watcher: self.watch_backend,
+            commands: self
+                .config
+                .commands
+                .iter()
+                .map(|c| c.name.clone())
+                .collect(),
+            paused: self.pause.mode(),
         };
         self.broadcaster.set_state(id, State::ProjectInfo(info))
     }
 
-    fn set_client_runner_state(&mut self, id: u32) {
+    fn set_client_runner_state(&mut self, id: Option<u32>) {
         self.broadcaster
-            .set_state(Some(id), State::Runners(Runners::default()));
+            .set_state(id, State::Runners(Runners::default()));
     }
 }
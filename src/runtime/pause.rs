@@ -0,0 +1,93 @@
+use crate::{EventBatch, PauseMode};
+
+/// State of file changes processing while watching is paused
+#[derive(Debug, Default)]
+pub struct WatchPause {
+    /// How file changes are handled, None if not paused
+    mode: Option<PauseMode>,
+    /// File changes buffered while paused with [`PauseMode::Buffer`]
+    batch: EventBatch,
+    /// Whether any file changes were received while paused, buffered or dropped
+    changed: bool,
+}
+
+/// Reconciliation to run once watching is resumed
+#[derive(Debug)]
+pub enum Reconcile {
+    /// Process buffered changes as a single batch
+    Batch(EventBatch),
+    /// Changes were dropped, ensure project setup and re-trigger all watch subscribers
+    Full,
+}
+
+impl WatchPause {
+    /// How file changes are handled, None if not paused
+    pub fn mode(&self) -> Option<PauseMode> {
+        self.mode
+    }
+
+    /// Pause processing file changes, changes buffered so far are dropped when switching to
+    /// [`PauseMode::Drop`]
+    pub fn pause(&mut self, mode: PauseMode) {
+        if mode == PauseMode::Drop {
+            self.batch = EventBatch::default();
+        }
+        self.mode = Some(mode);
+    }
+
+    /// Record file changes received while paused, the batch is given back if not paused
+    pub fn record(&mut self, batch: EventBatch) -> Option<EventBatch> {
+        let Some(mode) = self.mode else {
+            return Some(batch);
+        };
+
+        self.changed |= !batch.is_empty();
+        if mode == PauseMode::Buffer {
+            self.batch.append(batch);
+        }
+        None
+    }
+
+    /// Resume processing file changes and get reconciliation to run if anything changed
+    pub fn resume(&mut self) -> Option<Reconcile> {
+        let mode = self.mode.take()?;
+        let batch = std::mem::take(&mut self.batch);
+        if !std::mem::take(&mut self.changed) {
+            return None;
+        }
+
+        match mode {
+            PauseMode::Buffer if !batch.is_empty() => Some(Reconcile::Batch(batch)),
+            _ => Some(Reconcile::Full),
+        }
+    }
+}
+
+#[test]
+fn test_watch_pause_reconcile() {
+    use crate::util::fs::TempRoot;
+    use notify::event::{CreateKind, EventKind as NotifyEventKind};
+
+    let root = TempRoot::new("xbase-test-pause");
+    let kind = NotifyEventKind::Create(CreateKind::File);
+    let batch = |path: &str| EventBatch::from_paths(&root, kind.clone(), &[path]);
+    let mut pause = WatchPause::default();
+
+    assert!(pause.record(batch("A.swift")).is_some());
+    assert!(pause.resume().is_none());
+
+    pause.pause(PauseMode::Buffer);
+    assert!(pause.resume().is_none(), "nothing changed while paused");
+
+    pause.pause(PauseMode::Buffer);
+    assert!(pause.record(batch("A.swift")).is_none());
+    assert!(pause.record(batch("B.swift")).is_none());
+    assert_eq!(pause.mode(), Some(PauseMode::Buffer));
+    assert!(matches!(pause.resume(), Some(Reconcile::Batch(batch)) if batch.len() == 2));
+    assert_eq!(pause.mode(), None);
+
+    pause.pause(PauseMode::Drop);
+    assert!(pause.record(batch("A.swift")).is_none());
+    assert!(matches!(pause.resume(), Some(Reconcile::Full)));
+    assert!(pause.resume().is_none());
+}
@@ -13,14 +13,16 @@ pub struct BuildRequest {
 }
 
 #[async_trait]
-impl RequestHandler<()> for BuildRequest {
-    async fn handle(self) -> Result<()> {
+impl RequestHandler<PathBuf> for BuildRequest {
+    async fn handle(self) -> Result<PathBuf> {
         tracing::trace!("{:#?}", self);
+        let log_path = task_log_path(&self.root, &TaskKind::Build, &self.settings.target)?;
         runtimes()
             .await
             .get(&self.root)
-            .ok_or_else(|| Error::UnknownProject(self.root.clone()))
-            .map(|r| r.send(PRMessage::Build(self)))
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))?
+            .send(PRMessage::Build(self));
+        Ok(log_path)
     }
 }
 
@@ -32,18 +34,28 @@ impl Display for BuildRequest {
 
 #[async_trait]
 impl Watchable for BuildRequest {
-    async fn trigger(&self, p: &mut ProjectImpl, _: &Event, b: &Arc<Broadcast>) -> Result<()> {
-        p.build(&self.settings, None, b)?;
-        Ok(())
+    async fn trigger(
+        &self,
+        p: &mut ProjectImpl,
+        e: &EventBatch,
+        b: &Arc<Broadcast>,
+    ) -> Result<WatchJob> {
+        let (_, mut recv) = p.build(&self.settings, None, e, b)?;
+        Ok(Box::pin(async move {
+            recv.recv().await;
+            Ok(())
+        }))
     }
 
     /// A function that controls whether a a Watchable should restart
-    async fn should_trigger(&self, event: &Event) -> bool {
-        event.is_any_but_not_seen()
+    async fn should_trigger(&self, project: &ProjectImpl, event: &EventBatch) -> bool {
+        project
+            .target_sources()
+            .should_trigger(&self.settings.target, event)
     }
 
     /// A function that controls whether a watchable should be droped
-    async fn should_discard(&self, _event: &Event) -> bool {
+    async fn should_discard(&self, _event: &EventBatch) -> bool {
         false
     }
 
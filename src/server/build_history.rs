@@ -0,0 +1,23 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to get recorded builds of a project root with per target averages and trends
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct BuildHistoryRequest {
+    pub root: PathBuf,
+    /// Only get builds of a given target
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+#[async_trait]
+impl RequestHandler<BuildHistory> for BuildHistoryRequest {
+    async fn handle(self) -> Result<BuildHistory> {
+        tracing::trace!("{:#?}", self);
+        let records = build_records(&self.root).await?;
+        Ok(BuildHistory::new(records, self.target.as_deref()))
+    }
+}
@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+use tokio::sync::oneshot;
+use {super::*, crate::*};
+
+/// Request to run or watch a command configured in project `.xbase.json`
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct CommandRequest {
+    pub root: PathBuf,
+    /// Name of the command, see [`CommandConfig`]
+    pub name: String,
+    pub operation: Operation,
+}
+
+#[async_trait]
+impl RequestHandler<PathBuf> for CommandRequest {
+    async fn handle(self) -> Result<PathBuf> {
+        tracing::trace!("{:#?}", self);
+        if !self.operation.is_stop() {
+            let (tx, rx) = oneshot::channel();
+            runtimes()
+                .await
+                .get(&self.root)
+                .ok_or_else(|| Error::UnknownProject(self.root.clone()))?
+                .send(PRMessage::CommandConfig(self.name.clone(), tx));
+            rx.await.ok().flatten().into_result("Command", &self.name)?;
+        }
+        let log_path = task_log_path(&self.root, &TaskKind::Command, &self.name)?;
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))?
+            .send(PRMessage::Command(self));
+        Ok(log_path)
+    }
+}
+
+impl Display for CommandRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:Command:{}", self.root.display(), self.name)
+    }
+}
+
+impl CommandRequest {
+    /// Get watchable of requested command with its configuration
+    pub fn into_watchable(self, config: CommandConfig) -> Result<CommandWatchable> {
+        CommandWatchable::new(self.to_string(), &self.root, config)
+    }
+}
@@ -0,0 +1,56 @@
+use crate::*;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+use tokio::sync::Notify;
+use typescript_type_def::TypeDef;
+
+/// Time the daemon started at
+pub static STARTED_AT: Lazy<SystemTime> = Lazy::new(SystemTime::now);
+
+/// Number of clients currently connected to the daemon
+pub static CONNECTED_CLIENTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Notifier to gracefully stop the daemon
+pub static SHUTDOWN: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// Daemon health information
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct DaemonStatus {
+    /// Daemon process id
+    pub pid: u32,
+    /// Daemon version
+    pub version: String,
+    /// Seconds since the daemon started
+    pub uptime: u64,
+    /// Registered project roots
+    pub roots: Vec<PathBuf>,
+    /// Number of connected clients
+    pub clients: usize,
+    /// Tasks currently under progress
+    pub tasks: Vec<TaskInfo>,
+}
+
+/// Get current daemon status
+pub async fn status() -> Result<DaemonStatus> {
+    let roots = runtimes().await.keys().cloned().collect();
+    let uptime = STARTED_AT.elapsed().unwrap_or_default().as_secs();
+
+    Ok(DaemonStatus {
+        pid: std::process::id(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        uptime,
+        roots,
+        clients: CONNECTED_CLIENTS.load(Ordering::SeqCst),
+        tasks: active_tasks(),
+    })
+}
+
+/// Request the daemon to gracefully stop
+pub async fn shutdown() -> Result<()> {
+    tracing::warn!("SERVER STOPPED: Shutdown Requested");
+    SHUTDOWN.notify_one();
+    Ok(())
+}
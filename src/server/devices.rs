@@ -0,0 +1,33 @@
+use crate::*;
+use std::time::Duration;
+
+/// Re-discover available devices and update registered projects runners state on changes
+pub async fn refresh_devices() -> Result<Runners> {
+    let changed = tokio::task::spawn_blocking(Devices::refresh)
+        .await
+        .map_err(|e| Error::JoinError(e.to_string()))??;
+
+    if changed {
+        tracing::info!("Devices changed, updating runners state");
+        for runtime in runtimes().await.values() {
+            runtime.send(PRMessage::RefreshRunners);
+        }
+    }
+
+    Ok(Runners::default())
+}
+
+/// Future that periodically re-discover available devices
+pub async fn watch_devices(period: Duration) {
+    let mut interval = tokio::time::interval(period);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    // Skip first immediate tick, devices are discovered on first access
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        if let Err(err) = refresh_devices().await {
+            tracing::error!("{err}");
+        }
+    }
+}
@@ -5,16 +5,18 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 /// Drop a given set of roots to be dropped (i.e. unregistered)
-#[derive(Debug, Serialize, Deserialize, TypeDef)]
+#[derive(Debug, Default, Serialize, Deserialize, TypeDef)]
 pub struct DropRequest {
-    pub id: u32,
     pub roots: Vec<PathBuf>,
+    /// Session dropping the roots, set by the daemon
+    #[serde(skip)]
+    pub session: u32,
 }
 
 #[async_trait]
 impl RequestHandler<()> for DropRequest {
     async fn handle(self) -> Result<()> {
-        let DropRequest { roots, id } = self;
+        let DropRequest { roots, session } = self;
         let mut runtimes = runtimes().await;
         let mut drop_runtimes = vec![];
 
@@ -23,7 +25,7 @@ impl RequestHandler<()> for DropRequest {
                 continue;
             }
             let runtime = runtimes.get_mut(&root).unwrap();
-            runtime.disconnect(id.clone());
+            runtime.disconnect(session);
             if runtime.is_closed() {
                 drop_runtimes.push(root)
             }
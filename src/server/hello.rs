@@ -0,0 +1,71 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use strum::VariantNames;
+
+/// Protocol version spoken by the daemon, bumped on breaking changes to requests or messages
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Oldest client protocol version the daemon is still compatible with
+pub const MIN_PROTOCOL_VERSION: u32 = 2;
+
+/// Introduce client to the daemon, must be sent first on a new connection
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct HelloRequest {
+    /// Client name (e.g. nvim, vscode)
+    pub client_name: String,
+    /// Protocol version the client bindings were generated with
+    pub protocol_version: u32,
+    /// Optional message kinds the client can process, see [`Message::capability`]
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+/// Daemon version along with supported requests and messages
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct HelloResponse {
+    pub version: String,
+    pub protocol_version: u32,
+    pub requests: Vec<String>,
+    pub messages: Vec<String>,
+    /// Capabilities clients can declare, see [`Message::capability`]
+    pub capabilities: Vec<String>,
+}
+
+#[async_trait]
+impl RequestHandler<HelloResponse> for HelloRequest {
+    async fn handle(self) -> Result<HelloResponse> {
+        let Self {
+            client_name,
+            protocol_version,
+            ..
+        } = self;
+
+        if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&protocol_version) {
+            let outdated = if protocol_version < MIN_PROTOCOL_VERSION {
+                client_name.as_str()
+            } else {
+                "xbase"
+            };
+            return Err(Error::IncompatibleProtocol(format!(
+                "{client_name} uses v{protocol_version} while xbase supports \
+                 v{MIN_PROTOCOL_VERSION}..=v{PROTOCOL_VERSION}, please update {outdated}"
+            )));
+        }
+
+        tracing::info!("Hello {client_name} (v{protocol_version})");
+
+        Ok(HelloResponse {
+            version: env!("CARGO_PKG_VERSION").into(),
+            protocol_version: PROTOCOL_VERSION,
+            requests: Request::VARIANTS.iter().map(|r| r.to_string()).collect(),
+            messages: Message::VARIANTS
+                .iter()
+                .filter(|m| !matches!(**m, "Connect" | "Attach" | "Disconnect"))
+                .map(|m| m.to_string())
+                .collect(),
+            capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+        })
+    }
+}
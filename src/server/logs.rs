@@ -0,0 +1,39 @@
+use super::*;
+use crate::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Request to list past build and run logs of a project root
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct LogsRequest {
+    pub root: PathBuf,
+}
+
+#[async_trait]
+impl RequestHandler<Vec<TaskLogInfo>> for LogsRequest {
+    async fn handle(self) -> Result<Vec<TaskLogInfo>> {
+        tracing::trace!("{:#?}", self);
+        task_logs(&self.root)
+    }
+}
+
+/// Request to get content of a past log by name, see [`LogsRequest`]
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct ReadLogRequest {
+    pub root: PathBuf,
+    pub name: String,
+}
+
+#[async_trait]
+impl RequestHandler<String> for ReadLogRequest {
+    async fn handle(self) -> Result<String> {
+        tracing::trace!("{:#?}", self);
+        let log = task_logs(&self.root)?
+            .into_iter()
+            .find(|log| log.name == self.name)
+            .into_result("Log", &self.name)?;
+
+        Ok(tokio::fs::read_to_string(log.path).await?)
+    }
+}
@@ -1,24 +1,32 @@
 mod build;
+mod build_history;
+mod command;
+mod daemon;
+mod devices;
 mod drop;
+mod hello;
+mod logs;
 mod register;
 mod request;
 mod response;
 mod run;
+mod session;
+mod simulator;
+mod watch;
 
+use futures::{SinkExt, StreamExt};
 use std::os::unix::prelude::AsRawFd;
-use tokio::net::unix::{ReadHalf, WriteHalf};
-use tokio_serde::{formats::*, SymmetricallyFramed};
-use tokio_util::codec::{BytesCodec, FramedRead, FramedWrite};
+use tokio::net::unix::OwnedWriteHalf;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinSet;
+use tokio_util::codec::{FramedRead, FramedWrite, LinesCodec};
 use tracing::instrument;
 use typescript_type_def::TypeDef;
 
-pub use {build::*, drop::*, register::*, request::*, response::*, run::*};
-
-/// Stream of Requests to read Requests from
-struct RequestStream;
-
-/// Stream of Responses to write response to
-struct ResponseStream;
+pub use {
+    build::*, build_history::*, command::*, daemon::*, devices::*, drop::*, hello::*, logs::*,
+    register::*, request::*, response::*, run::*, session::*, simulator::*, watch::*,
+};
 
 /// Trait that must be implemented by All Request members
 #[async_trait::async_trait]
@@ -26,53 +34,132 @@ pub trait RequestHandler<T: serde::Serialize> {
     async fn handle(self) -> crate::Result<T>;
 }
 
-/// Generic over T and Framed Write/Read
-type F<T, F> = SymmetricallyFramed<F, T, SymmetricalJson<T>>;
-
-impl RequestStream {
-    fn new<'a>(r: ReadHalf<'a>) -> F<Request, FramedRead<ReadHalf<'a>, BytesCodec>> {
-        let transport = FramedRead::new(r, BytesCodec::default());
-        F::new(transport, SymmetricalJson::default())
-    }
-}
-
-impl ResponseStream {
-    fn new<'a>(w: WriteHalf<'a>) -> F<Response, FramedWrite<WriteHalf<'a>, BytesCodec>> {
-        let transport = FramedWrite::new(w, BytesCodec::default());
-        F::new(transport, SymmetricalJson::default())
-    }
-}
-
 /// Future that await and process client requests.
+///
+/// Requests and responses are JSON objects delimited by new lines. Requests are processed
+/// concurrently and responses are written as soon as they are ready, tagged with request id.
+/// Requests other than [`HelloRequest`] are rejected till a compatible hello is accepted.
+/// Messages of registered projects are pushed on the same connection as [`Notification`]s.
 #[instrument(parent = None, name = "Client", skip_all, fields(fd = stream.as_raw_fd()))]
-pub async fn handle(mut stream: tokio::net::UnixStream) {
-    use futures::{SinkExt, TryStreamExt};
+pub async fn handle(stream: tokio::net::UnixStream) {
     use tracing::{error, info};
-    info!("Connected");
+    CONNECTED_CLIENTS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
 
     // Client Registered roots
     let mut roots = vec![];
-    let (reader, writer) = stream.split();
-    let (mut reader, mut writer) = (RequestStream::new(reader), ResponseStream::new(writer));
-    let mut id = 0;
+    // Whether client hello got accepted
+    let mut greeted = false;
+    let (reader, writer) = stream.into_split();
+    let mut reader = FramedRead::new(reader, LinesCodec::new());
+    let (tx, rx) = unbounded_channel();
+    let writer = tokio::spawn(write_lines(writer, rx));
+    let mut pending = JoinSet::new();
+    let session = add_session(tx.clone());
+    info!("Connected [{session}]");
 
     loop {
-        match reader.try_next().await {
-            Ok(Some(request)) => {
-                if let Request::Register(r) = &request {
-                    id = r.id;
-                    roots.push(r.root.clone())
-                };
-                let response = request.handle().await;
-                let send_res = writer.send(response).await;
-                send_res.map_err(|err| error!("Send Error: {err}")).ok();
-            }
-            Err(err) => error!("Read Error: {err:#?}"),
-            Ok(None) => {
-                Request::Drop(DropRequest { id, roots }).handle().await;
-                break;
-            }
+        tokio::select! {
+            line = reader.next() => match line {
+                Some(Ok(line)) if line.trim().is_empty() => continue,
+                Some(Ok(line)) => {
+                    let mut message = match serde_json::from_str::<RequestMessage>(&line) {
+                        Ok(message) => message,
+                        Err(err) => {
+                            error!("Parse Error: {err}");
+                            let mut response = Response::parse_error(&line, err);
+                            if !greeted {
+                                response = hello_required(response.id);
+                            }
+                            send_response(&tx, response);
+                            continue;
+                        }
+                    };
+                    // Hello is processed in order so following requests see its outcome
+                    if let Request::Hello(hello) = message.request {
+                        let capabilities = hello.capabilities.clone();
+                        let result = hello.handle().await;
+                        // Capabilities are only stored once protocol version is accepted
+                        if result.is_ok() {
+                            set_session_capabilities(session, &capabilities);
+                            greeted = true;
+                        }
+                        let mut response = Response::new(result);
+                        response.id = message.id;
+                        send_response(&tx, response);
+                        continue;
+                    } else if !greeted {
+                        send_response(&tx, hello_required(message.id));
+                        continue;
+                    }
+                    let mut registered = None;
+                    match &mut message.request {
+                        Request::Register(r) => {
+                            r.session = session;
+                            roots.push(r.root.clone());
+                            registered = Some(r.root.clone());
+                        }
+                        Request::Drop(r) => r.session = session,
+                        _ => {}
+                    };
+                    let tx = tx.clone();
+                    pending.spawn(async move {
+                        let mut response = message.request.handle().await;
+                        response.id = message.id;
+                        send_response(&tx, response);
+                        // Project messages are only written after the register response
+                        if let Some(root) = registered {
+                            attach_session(&root, session).await;
+                        }
+                    });
+                }
+                Some(Err(err)) => {
+                    error!("Read Error: {err}");
+                    break;
+                }
+                None => break,
+            },
+            Some(_) = pending.join_next() => {}
+        }
+    }
+
+    // Wait for in-flight requests so registrations made by them get dropped too
+    while pending.join_next().await.is_some() {}
+    Request::Drop(DropRequest { session, roots }).handle().await;
+    remove_session(session);
+
+    drop(tx);
+    writer.await.ok();
+
+    CONNECTED_CLIENTS.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+    info!("Disconnected [{session}]");
+}
+
+/// Error response to a request received before client hello got accepted
+fn hello_required(id: u32) -> Response {
+    let error = crate::Error::IncompatibleProtocol(
+        "hello request with a supported protocol version must be sent first".into(),
+    );
+    let mut response = Response::new::<()>(Err(error));
+    response.id = id;
+    response
+}
+
+/// Serialize and queue response to be written on client connection
+fn send_response(tx: &UnboundedSender<String>, response: Response) {
+    match serde_json::to_string(&response) {
+        Ok(line) => {
+            tx.send(line).ok();
+        }
+        Err(err) => tracing::error!("Serialize Error: {err}"),
+    }
+}
+
+/// Write lines to client connection till all senders are dropped
+async fn write_lines(writer: OwnedWriteHalf, mut rx: UnboundedReceiver<String>) {
+    let mut writer = FramedWrite::new(writer, LinesCodec::new());
+    while let Some(line) = rx.recv().await {
+        if let Err(err) = writer.send(line).await {
+            tracing::error!("Send Error: {err}");
         }
     }
-    info!("Disconnected");
 }
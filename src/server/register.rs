@@ -3,38 +3,39 @@ use crate::runtime::ProjectRuntime;
 use crate::*;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::{path::PathBuf, time::Duration};
+use std::path::{Path, PathBuf};
 
 /// Register a project root
-#[derive(Debug, Serialize, Deserialize, TypeDef)]
+#[derive(Debug, Default, Serialize, Deserialize, TypeDef)]
 pub struct RegisterRequest {
-    pub id: u32,
     pub root: PathBuf,
+    /// Replay recent project logs and tasks matching a given filter
+    #[serde(default)]
+    pub replay: ReplayFilter,
+    /// Skip replaying recent project logs and tasks
+    #[serde(default)]
+    pub no_replay: bool,
+    /// Session registering the root, set by the daemon
+    #[serde(skip)]
+    pub session: u32,
 }
 
 #[async_trait]
-impl RequestHandler<PathBuf> for RegisterRequest {
-    async fn handle(self) -> Result<PathBuf> {
-        let RegisterRequest { id, root } = self;
-        let mut runtimes = runtimes().await;
-        tracing::trace!("{:#?}", runtimes);
-
-        if let Some(runtime) = runtimes.get_mut(&root) {
-            if runtime.contains(&id) {
-                return Err(Error::Unexpected(
-                    "Trying to adding a connected client!".into(),
-                ));
-            }
+impl RequestHandler<()> for RegisterRequest {
+    async fn handle(self) -> Result<()> {
+        let RegisterRequest {
+            root,
+            replay,
+            no_replay,
+            session,
+        } = self;
+        let replay = (!no_replay).then_some(replay);
 
-            let address = runtime.broadcaster_adderss().clone();
-            tokio::spawn(async move {
-                tokio::time::sleep(Duration::from_millis(100)).await;
-                runtimes.get_mut(&root).unwrap().connect(id);
-            });
-
-            return Ok(address);
+        if let Some(runtime) = runtimes().await.get_mut(&root) {
+            return connect_runtime(runtime, &root, session, replay);
         }
 
+        // Runtimes aren't locked while the project loads, so requests of other roots proceed
         let (rloop, mut runtime) = match ProjectRuntime::new(root.clone()).await {
             Ok(v) => v,
             Err(err) => {
@@ -43,16 +44,118 @@ impl RequestHandler<PathBuf> for RegisterRequest {
             }
         };
 
-        let address = runtime.broadcaster_adderss().clone();
+        let mut runtimes = runtimes().await;
+        tracing::trace!("{:#?}", runtimes);
+
+        // Root got registered by another client while the project was loading
+        if let Some(runtime) = runtimes.get_mut(&root) {
+            return connect_runtime(runtime, &root, session, replay);
+        }
+
+        runtime.connect(session, replay);
+        runtimes.insert(root, runtime);
+        drop(runtimes);
+        tokio::spawn(rloop.start());
+
+        Ok(())
+    }
+}
+
+/// Connect a session to an already running project runtime
+fn connect_runtime(
+    runtime: &mut PRMessageSender,
+    root: &Path,
+    session: u32,
+    replay: Option<ReplayFilter>,
+) -> Result<()> {
+    if runtime.contains(&session) {
+        let msg = format!("{root:?} is already registered");
+        return Err(Error::Unexpected(msg));
+    }
 
-        tokio::spawn(async move {
-            tokio::time::sleep(Duration::from_millis(100)).await;
-            runtime.insert(id);
-            runtimes.insert(root, runtime);
-            drop(runtimes);
-            rloop.start(id).await;
-        });
+    runtime.connect(session, replay);
+    Ok(())
+}
 
-        Ok(address)
+/// Start writing project messages of a registered root to a given session.
+///
+/// Called once the register response is written, so clients always get the response before
+/// the project initial state. Messages sent before then are queued by the broadcaster.
+pub async fn attach_session(root: &Path, session: u32) {
+    if let Some(runtime) = runtimes().await.get(root) {
+        runtime.attach(session);
     }
 }
+
+#[test]
+fn test_register_replay_by_default() {
+    let request = |args| serde_json::from_value::<RegisterRequest>(args).unwrap();
+
+    let register = request(serde_json::json!({ "root": "/tmp/Demo" }));
+    assert!(!register.no_replay);
+    assert!(register.replay.level.is_none() && register.replay.task.is_none());
+
+    let register = request(serde_json::json!({ "root": "/tmp/Demo", "no_replay": true }));
+    assert!(register.no_replay);
+
+    let args = serde_json::json!({ "root": "/tmp/Demo", "replay": { "task": 2 } });
+    assert_eq!(request(args).replay.task, Some(2));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_status_not_blocked_by_register() {
+    use std::fs::{create_dir_all, remove_dir_all, rename, write, OpenOptions};
+    use std::io::Write;
+    use std::os::unix::ffi::OsStrExt;
+    use std::time::Duration;
+
+    let root = std::env::temp_dir().join("xbase-test-register-lock/Demo");
+    remove_dir_all(&root).ok();
+    create_dir_all(root.join("Demo.xcodeproj")).unwrap();
+
+    // Loading the project blocks on reading the fifo till the test writes into it
+    let pbxproj = root.join("Demo.xcodeproj/project.pbxproj");
+    let fifo = std::ffi::CString::new(pbxproj.as_os_str().as_bytes()).unwrap();
+    assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0);
+
+    let register = tokio::spawn(
+        RegisterRequest {
+            root: root.clone(),
+            no_replay: true,
+            session: u32::MAX,
+            ..Default::default()
+        }
+        .handle(),
+    );
+
+    // Give register time to take the runtimes lock, as it did before, and block on the fifo
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let result = tokio::time::timeout(Duration::from_secs(1), status()).await;
+    assert!(result.is_ok(), "status blocked by in progress register");
+    assert!(!register.is_finished());
+
+    // Whether loading such a project succeeds doesn't matter, only that it was waited for
+    let content = "// !$*UTF8*$!\n{\n}\n";
+    tokio::task::spawn_blocking({
+        let pbxproj = pbxproj.clone();
+        move || {
+            let mut writer = OpenOptions::new().write(true).open(&pbxproj).unwrap();
+            // Following reads get a regular file, the blocked reader keeps reading the fifo
+            let file = pbxproj.with_extension("tmp");
+            write(&file, content).unwrap();
+            rename(&file, &pbxproj).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+    })
+    .await
+    .unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), register)
+        .await
+        .expect("register finished once project is readable")
+        .unwrap()
+        .ok();
+
+    runtimes().await.remove(&root);
+    remove_dir_all(root.parent().unwrap()).ok();
+}
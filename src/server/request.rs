@@ -1,28 +1,79 @@
 use super::*;
 use serde::{Deserialize, Serialize};
+use strum::EnumVariantNames;
 use tap::Pipe;
 
-/// Requests clinets can make
+/// Request along with an id chosen by the client to match it with its response
 #[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct RequestMessage {
+    /// Request id, echoed back in the request response
+    #[serde(default)]
+    pub id: u32,
+    #[serde(flatten)]
+    pub request: Request,
+}
+
+/// Requests clinets can make
+#[derive(Debug, Serialize, Deserialize, TypeDef, EnumVariantNames)]
 #[serde(tag = "method", content = "args", rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
 pub enum Request {
-    /// Register project root and get broadcaster reader file description
+    /// Negotiate protocol version and capabilities, must be sent first on a new connection
+    Hello(HelloRequest),
+    /// Register project root and receive its messages as notifications on the same connection
     Register(RegisterRequest),
     /// Build Project and get path to where to build log will be located
     Build(BuildRequest),
     /// Run Project and get path to where to Runtime log will be located
     Run(RunRequest),
+    /// Run or watch a project command and get path to where its log will be located
+    Command(CommandRequest),
     /// Drop projects at a given roots
     Drop(DropRequest),
+    /// List past build and run logs of a project, newest first
+    Logs(LogsRequest),
+    /// Get content of a past build or run log
+    ReadLog(ReadLogRequest),
+    /// Get recorded builds of a project with per target averages and trends
+    BuildHistory(BuildHistoryRequest),
+    /// Pause processing file changes of a project, e.g. during a rebase
+    PauseWatch(PauseWatchRequest),
+    /// Resume processing file changes of a project, buffered changes are processed at once
+    ResumeWatch(ResumeWatchRequest),
+    /// Re-discover available devices and get updated runners
+    RefreshDevices,
+    /// Create, erase, delete, boot, shutdown or rename a simulator
+    SimulatorLifecycle(SimulatorLifecycleRequest),
+    /// Get runtimes and device types to create simulators with
+    SimulatorOptions,
+    /// Run utility action (open url, push, screenshot ...) on a simulator
+    Simulator(SimulatorRequest),
+    /// Get daemon status
+    Status,
+    /// Gracefully stop the daemon
+    Shutdown,
 }
 
 impl Request {
     pub async fn handle(self) -> Response {
         match self {
+            Request::Hello(req) => req.handle().await.pipe(Response::new),
             Request::Register(req) => req.handle().await.pipe(Response::new),
             Request::Build(req) => req.handle().await.pipe(Response::new),
             Request::Run(req) => req.handle().await.pipe(Response::new),
+            Request::Command(req) => req.handle().await.pipe(Response::new),
             Request::Drop(req) => req.handle().await.pipe(Response::new),
+            Request::Logs(req) => req.handle().await.pipe(Response::new),
+            Request::ReadLog(req) => req.handle().await.pipe(Response::new),
+            Request::BuildHistory(req) => req.handle().await.pipe(Response::new),
+            Request::PauseWatch(req) => req.handle().await.pipe(Response::new),
+            Request::ResumeWatch(req) => req.handle().await.pipe(Response::new),
+            Request::RefreshDevices => refresh_devices().await.pipe(Response::new),
+            Request::SimulatorLifecycle(req) => req.handle().await.pipe(Response::new),
+            Request::SimulatorOptions => simulator_options().await.pipe(Response::new),
+            Request::Simulator(req) => req.handle().await.pipe(Response::new),
+            Request::Status => status().await.pipe(Response::new),
+            Request::Shutdown => shutdown().await.pipe(Response::new),
         }
     }
 }
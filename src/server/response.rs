@@ -1,11 +1,14 @@
-use crate::{types::Result, ServerError};
-use serde::Serialize;
+use crate::{types::Result, Error, ServerError};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use typescript_type_def::TypeDef;
 
 /// Server Response
-#[derive(Default, Debug, Serialize, TypeDef)]
+#[derive(Default, Debug, Serialize, Deserialize, TypeDef)]
 pub struct Response {
+    /// Id of the request this response is for
+    #[serde(default)]
+    pub id: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     data: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -21,4 +24,23 @@ impl Response {
         };
         response
     }
+
+    /// Create an error response for a line that couldn't be parsed as a request
+    pub fn parse_error(line: &str, error: serde_json::Error) -> Response {
+        let id = serde_json::from_str::<Value>(line)
+            .ok()
+            .and_then(|v| v.get("id")?.as_u64())
+            .unwrap_or_default() as u32;
+        let mut response = Response::new::<()>(Err(Error::MessageParse(error.to_string())));
+        response.id = id;
+        response
+    }
+
+    /// Convert response into response data or the error the server responded with
+    pub fn into_result(self) -> Result<Value> {
+        match self.error {
+            Some(error) => Err(error.into()),
+            None => Ok(self.data.unwrap_or_default()),
+        }
+    }
 }
@@ -17,14 +17,16 @@ pub struct RunRequest {
 }
 
 #[async_trait]
-impl RequestHandler<()> for RunRequest {
-    async fn handle(self) -> Result<()> {
+impl RequestHandler<PathBuf> for RunRequest {
+    async fn handle(self) -> Result<PathBuf> {
         tracing::trace!("{:#?}", self);
+        let log_path = task_log_path(&self.root, &TaskKind::Run, &self.settings.target)?;
         runtimes()
             .await
             .get(&self.root)
-            .ok_or_else(|| Error::UnknownProject(self.root.clone()))
-            .map(|r| r.send(PRMessage::Run(self)))
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))?
+            .send(PRMessage::Run(self));
+        Ok(log_path)
     }
 }
 
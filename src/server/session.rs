@@ -0,0 +1,64 @@
+use crate::*;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Client connection state shared with project broadcasters.
+///
+/// Sessions are created by the daemon for each connection, registrations and broadcasts are tied
+/// to session ids and cleaned up once the connection is closed.
+#[derive(Debug)]
+pub struct Session {
+    /// Sender of lines to be written to client connection
+    sender: UnboundedSender<String>,
+    /// Capabilities declared in client hello request
+    capabilities: Vec<String>,
+}
+
+/// Sessions of connected clients indexed by session id
+static SESSIONS: Lazy<Mutex<HashMap<u32, Session>>> = Lazy::new(Default::default);
+
+/// Last issued session id
+static SESSION_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Create a session for a new client connection and get its id
+pub fn add_session(sender: UnboundedSender<String>) -> u32 {
+    let id = SESSION_ID.fetch_add(1, Ordering::SeqCst) + 1;
+    let session = Session {
+        sender,
+        capabilities: vec![],
+    };
+    SESSIONS.lock().unwrap().insert(id, session);
+    id
+}
+
+/// Set capabilities declared in client hello request
+pub fn set_session_capabilities(id: u32, capabilities: &[String]) {
+    if let Some(session) = SESSIONS.lock().unwrap().get_mut(&id) {
+        session.capabilities = capabilities.to_vec();
+    }
+}
+
+/// Forget session of a disconnected client
+pub fn remove_session(id: u32) {
+    SESSIONS.lock().unwrap().remove(&id);
+}
+
+/// Write serialized project message to a given session connection.
+///
+/// Messages gated behind a capability the client didn't declare are skipped. Returns false if
+/// the session is no longer connected.
+pub fn push_message(id: u32, capability: Option<&str>, line: &str) -> bool {
+    let sessions = SESSIONS.lock().unwrap();
+    let Some(session) = sessions.get(&id) else {
+        return false;
+    };
+
+    if let Some(capability) = capability {
+        if !session.capabilities.iter().any(|c| c == capability) {
+            return true;
+        }
+    }
+
+    session.sender.send(line.to_string()).is_ok()
+}
@@ -0,0 +1,371 @@
+use super::*;
+#[cfg(not(target_os = "macos"))]
+use crate::simctl;
+use crate::*;
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, Command};
+use tokio::sync::{oneshot, Mutex};
+
+/// Request to manage simulators lifecycle
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SimulatorLifecycleRequest {
+    /// Create a new simulator using identifiers from [`SimulatorOptions`]
+    Create {
+        name: String,
+        device_type: String,
+        runtime: String,
+    },
+    /// Erase simulator contents and settings
+    Erase { device: DeviceLookup },
+    /// Delete simulator
+    Delete { device: DeviceLookup },
+    /// Boot simulator
+    Boot { device: DeviceLookup },
+    /// Shutdown simulator
+    Shutdown { device: DeviceLookup },
+    /// Rename simulator
+    Rename { device: DeviceLookup, name: String },
+}
+
+/// Runtimes and device types that can be used to create new simulators
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct SimulatorOptions {
+    pub runtimes: Vec<SimulatorOption>,
+    pub device_types: Vec<SimulatorOption>,
+}
+
+/// Simulator runtime or device type
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct SimulatorOption {
+    pub name: String,
+    pub identifier: String,
+}
+
+#[async_trait]
+impl RequestHandler<DeviceLookup> for SimulatorLifecycleRequest {
+    async fn handle(self) -> Result<DeviceLookup> {
+        tracing::trace!("{:#?}", self);
+        let lookup = match self {
+            Self::Create {
+                name,
+                device_type,
+                runtime,
+            } => create_simulator(&name, &device_type, &runtime).await?,
+            Self::Rename { device, name } => {
+                let (id, new_name) = (device.id.clone(), name.clone());
+                with_device(device, move |d| d.rename(&new_name)).await?;
+                DeviceLookup::new(name, id)
+            }
+            Self::Erase { device } => with_device(device, |d| d.erase()).await?,
+            Self::Delete { device } => with_device(device, |d| d.delete()).await?,
+            Self::Boot { device } => with_device(device, |d| d.boot()).await?,
+            Self::Shutdown { device } => with_device(device, |d| d.shutdown()).await?,
+        };
+
+        refresh_devices().await?;
+
+        Ok(lookup)
+    }
+}
+
+/// Get runtimes and device types available for creating new simulators
+pub async fn simulator_options() -> Result<SimulatorOptions> {
+    tokio::task::spawn_blocking(|| {
+        let list = simctl::Simctl::new()
+            .list()
+            .map_err(|e| Error::DeviceDiscovery(simctl_error_message(e)))?;
+
+        let runtimes = list
+            .runtimes()
+            .iter()
+            .filter(|r| r.is_available)
+            .map(|r| SimulatorOption {
+                name: r.name.clone(),
+                identifier: r.identifier.clone(),
+            })
+            .collect();
+
+        let device_types = list
+            .device_types()
+            .iter()
+            .map(|t| SimulatorOption {
+                name: t.name.clone(),
+                identifier: t.identifier.clone(),
+            })
+            .collect();
+
+        Ok(SimulatorOptions {
+            runtimes,
+            device_types,
+        })
+    })
+    .await
+    .map_err(|e| Error::JoinError(e.to_string()))?
+}
+
+/// Run simctl operation on device matching the given lookup
+async fn with_device<F>(lookup: DeviceLookup, f: F) -> Result<DeviceLookup>
+where
+    F: FnOnce(&Device) -> simctl::Result<()> + Send + 'static,
+{
+    let device = Devices::from_lookup(Some(lookup.clone())).into_result("Device", &lookup.name)?;
+
+    tokio::task::spawn_blocking(move || f(&device))
+        .await
+        .map_err(|e| Error::JoinError(e.to_string()))?
+        .map_err(|e| Error::Simulator(simctl_error_message(e)))?;
+
+    Ok(lookup)
+}
+
+/// Create a new simulator and get its lookup
+async fn create_simulator(name: &str, device_type: &str, runtime: &str) -> Result<DeviceLookup> {
+    let output = Command::new(xcrun()?)
+        .args(["simctl", "create", name, device_type, runtime])
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Simulator(stderr.trim().replace('\n', " ")));
+    }
+
+    let id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    tracing::info!("Created {name} ({id})");
+
+    Ok(DeviceLookup::new(name.to_string(), id))
+}
+
+/// Request to run a utility action on a simulator
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct SimulatorRequest {
+    /// Project root to get the device of an active run from
+    #[serde(default)]
+    pub root: Option<PathBuf>,
+    /// Device to run the action against, default to the device of an active run of the project
+    /// or the booted simulator
+    #[serde(default)]
+    pub device: Option<DeviceLookup>,
+    pub action: SimulatorAction,
+}
+
+/// Simulator utility actions
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SimulatorAction {
+    /// Open url on the simulator
+    OpenUrl { url: String },
+    /// Send a push notification payload to an app
+    Push { bundle_id: String, payload: Value },
+    /// Set simulated location
+    Location { latitude: f64, longitude: f64 },
+    /// Add photos, live photos, videos, or contacts to the simulator library
+    AddMedia { paths: Vec<PathBuf> },
+    /// Take a screenshot, default to a path in xbase cache directory
+    Screenshot {
+        #[serde(default)]
+        path: Option<PathBuf>,
+    },
+    /// Start recording a video, default to a path in xbase cache directory
+    StartRecording {
+        #[serde(default)]
+        path: Option<PathBuf>,
+    },
+    /// Stop recording a video and get the recording path
+    StopRecording,
+    /// Grant an app access to a privacy service (e.g. photos, location)
+    Grant { service: String, bundle_id: String },
+}
+
+/// Active video recordings indexed by device udid
+static RECORDINGS: Lazy<Mutex<HashMap<String, (Child, PathBuf)>>> = Lazy::new(Default::default);
+
+#[async_trait]
+impl RequestHandler<Option<PathBuf>> for SimulatorRequest {
+    async fn handle(self) -> Result<Option<PathBuf>> {
+        tracing::trace!("{:#?}", self);
+        let Self {
+            root,
+            device,
+            action,
+        } = self;
+
+        let device = match (device, root) {
+            (Some(lookup), _) => {
+                Devices::from_lookup(Some(lookup.clone())).into_result("Device", &lookup.name)?
+            }
+            (None, Some(root)) => match run_device(&root).await? {
+                Some(device) => device,
+                None => booted_device().await?,
+            },
+            (None, None) => booted_device().await?,
+        };
+
+        action.run(&device).await
+    }
+}
+
+/// Get device of an active run of a given project
+async fn run_device(root: &Path) -> Result<Option<Device>> {
+    let (tx, rx) = oneshot::channel();
+    runtimes()
+        .await
+        .get(root)
+        .ok_or_else(|| Error::UnknownProject(root.to_path_buf()))?
+        .send(PRMessage::RunDevice(tx));
+    Ok(rx.await.ok().flatten())
+}
+
+/// Get the booted simulator, failing when none or several simulators are booted
+async fn booted_device() -> Result<Device> {
+    xcrun()?;
+    let devices = tokio::task::spawn_blocking(Devices::discover)
+        .await
+        .map_err(|e| Error::JoinError(e.to_string()))??;
+    let mut booted = devices
+        .values()
+        .filter(|device| device.state == simctl::DeviceState::Booted)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    match booted.len() {
+        0 => Err(Error::Simulator(
+            "No running app or booted simulator found, pass a device".into(),
+        )),
+        1 => Ok(booted.remove(0)),
+        _ => {
+            let names = booted.iter().map(ToString::to_string).collect::<Vec<_>>();
+            let msg = format!(
+                "Multiple booted simulators ({}), pass a device",
+                names.join(", ")
+            );
+            Err(Error::Simulator(msg))
+        }
+    }
+}
+
+impl SimulatorAction {
+    /// Run action against a given device and get output path if any
+    async fn run(self, device: &Device) -> Result<Option<PathBuf>> {
+        match self {
+            Self::OpenUrl { url } => {
+                let device = device.clone();
+                tokio::task::spawn_blocking(move || device.open_url(&url))
+                    .await
+                    .map_err(|e| Error::JoinError(e.to_string()))?
+                    .map_err(|e| Error::Simulator(simctl_error_message(e)))?;
+            }
+            Self::Push { bundle_id, payload } => {
+                let mut child = device
+                    .simctl("push")?
+                    .args([&bundle_id, "-"])
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(payload.to_string().as_bytes()).await?;
+                }
+                ensure_success(child.wait_with_output().await?)?;
+            }
+            Self::Location {
+                latitude,
+                longitude,
+            } => {
+                let mut command = device.simctl("location")?;
+                command.args(["set", &format!("{latitude},{longitude}")]);
+                ensure_success(command.output().await?)?;
+            }
+            Self::AddMedia { paths } => {
+                let mut command = device.simctl("addmedia")?;
+                command.args(paths);
+                ensure_success(command.output().await?)?;
+            }
+            Self::Screenshot { path } => {
+                let path = output_path(path, device, "png")?;
+                let mut command = device.simctl("io")?;
+                command.arg("screenshot").arg(&path);
+                ensure_success(command.output().await?)?;
+                return Ok(Some(path));
+            }
+            Self::StartRecording { path } => {
+                let mut recordings = RECORDINGS.lock().await;
+                if recordings.contains_key(&device.udid) {
+                    let msg = format!("{device} is already being recorded");
+                    return Err(Error::Simulator(msg));
+                }
+                let path = output_path(path, device, "mp4")?;
+                let child = device
+                    .simctl("io")?
+                    .arg("recordVideo")
+                    .arg("--force")
+                    .arg(&path)
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()?;
+                recordings.insert(device.udid.clone(), (child, path.clone()));
+                return Ok(Some(path));
+            }
+            Self::StopRecording => {
+                let (mut child, path) = RECORDINGS
+                    .lock()
+                    .await
+                    .remove(&device.udid)
+                    .into_result("Recording", &device.name)?;
+                // recordVideo only finalize the video file on interrupt
+                if let Some(pid) = child.id() {
+                    unsafe { libc::kill(pid as i32, libc::SIGINT) };
+                }
+                child.wait().await?;
+                return Ok(Some(path));
+            }
+            Self::Grant { service, bundle_id } => {
+                let mut command = device.simctl("privacy")?;
+                command.args(["grant", &service, &bundle_id]);
+                ensure_success(command.output().await?)?;
+            }
+        };
+
+        Ok(None)
+    }
+}
+
+/// Return an error with stderr content if simctl command failed
+fn ensure_success(output: Output) -> Result<()> {
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(Error::Simulator(stderr.trim().replace('\n', " ")))
+    }
+}
+
+/// Get given path or generate a new one under xbase cache directory
+fn output_path(path: Option<PathBuf>, device: &Device, extension: &str) -> Result<PathBuf> {
+    if let Some(path) = path {
+        return Ok(path);
+    }
+
+    let root = dirs::cache_dir()
+        .ok_or_else(|| Error::Unexpected("Unable to get cache directory".into()))?
+        .join("Xbase")
+        .join("Simulator");
+    std::fs::create_dir_all(&root)?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let name = device.name.replace(' ', "_");
+
+    Ok(root.join(format!("{name}_{timestamp}.{extension}")))
+}
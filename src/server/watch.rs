@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use {super::*, crate::*};
+
+/// Request to pause processing file changes of a project
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct PauseWatchRequest {
+    pub root: PathBuf,
+    /// Whether changes are buffered till watching is resumed or dropped, default buffer
+    #[serde(default)]
+    pub mode: PauseMode,
+}
+
+/// Request to resume processing file changes of a paused project
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
+pub struct ResumeWatchRequest {
+    pub root: PathBuf,
+}
+
+#[async_trait]
+impl RequestHandler<()> for PauseWatchRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::trace!("{:#?}", self);
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))?
+            .send(PRMessage::PauseWatch(self.mode));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RequestHandler<()> for ResumeWatchRequest {
+    async fn handle(self) -> Result<()> {
+        tracing::trace!("{:#?}", self);
+        runtimes()
+            .await
+            .get(&self.root)
+            .ok_or_else(|| Error::UnknownProject(self.root.clone()))?
+            .send(PRMessage::ResumeWatch);
+        Ok(())
+    }
+}
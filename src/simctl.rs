@@ -0,0 +1,119 @@
+//! Stand-in for the `simctl` crate on platforms without Xcode, see [`Simctl`].
+//!
+//! Mirrors the subset of `simctl` api used by the daemon. No devices are ever discovered, so
+//! device operations always fail with [`Error::Io`] of kind [`std::io::ErrorKind::Unsupported`].
+#![allow(dead_code)]
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum Error {
+    Output { stdout: String, stderr: String },
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Utf8(std::string::FromUtf8Error),
+}
+
+fn unsupported<T>() -> Result<T> {
+    let kind = std::io::ErrorKind::Unsupported;
+    Err(Error::Io(std::io::Error::new(
+        kind,
+        "simctl is only available on macOS",
+    )))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceState {
+    Booted,
+    Shutdown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Device {
+    pub udid: String,
+    pub name: String,
+    pub state: DeviceState,
+    pub is_available: bool,
+    pub runtime_identifier: String,
+}
+
+impl Device {
+    pub fn boot(&self) -> Result<()> {
+        unsupported()
+    }
+
+    pub fn shutdown(&self) -> Result<()> {
+        unsupported()
+    }
+
+    pub fn erase(&self) -> Result<()> {
+        unsupported()
+    }
+
+    pub fn delete(&self) -> Result<()> {
+        unsupported()
+    }
+
+    pub fn rename(&self, _name: &str) -> Result<()> {
+        unsupported()
+    }
+
+    pub fn open_url(&self, _url: &str) -> Result<()> {
+        unsupported()
+    }
+
+    pub fn install(&self, _path: &Path) -> Result<()> {
+        unsupported()
+    }
+}
+
+#[derive(Debug)]
+pub struct Runtime {
+    pub name: String,
+    pub identifier: String,
+    pub is_available: bool,
+}
+
+#[derive(Debug)]
+pub struct DeviceType {
+    pub name: String,
+    pub identifier: String,
+}
+
+#[derive(Debug, Default)]
+pub struct List {
+    devices: Vec<Device>,
+    runtimes: Vec<Runtime>,
+    device_types: Vec<DeviceType>,
+}
+
+impl List {
+    pub fn devices(&self) -> &Vec<Device> {
+        &self.devices
+    }
+
+    pub fn runtimes(&self) -> &Vec<Runtime> {
+        &self.runtimes
+    }
+
+    pub fn device_types(&self) -> &Vec<DeviceType> {
+        &self.device_types
+    }
+}
+
+/// Simulators controller, `xcrun simctl` is unavailable so listing always fails
+#[derive(Debug, Default)]
+pub struct Simctl;
+
+impl Simctl {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn list(&self) -> Result<List> {
+        unsupported()
+    }
+}
@@ -1,4 +1,5 @@
 use crate::error::*;
+use crate::watcher::WatchBackend;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fmt::Display};
 use strum::{Display as EnumDisplay, EnumString};
@@ -7,12 +8,18 @@ use xcodeproj::pbxproj::PBXTargetInfo;
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-#[derive(Debug, Serialize, TypeDef)]
+#[derive(Debug, Serialize, Deserialize, TypeDef)]
 pub struct ProjectInfo {
     /// Get watched configurations for given root
     pub watchlist: Vec<String>,
     /// Get targets information for a registers project with a given root
     pub targets: HashMap<String, TargetInfo>,
+    /// Active file watcher backend, None till the watcher is started
+    pub watcher: Option<WatchBackend>,
+    /// Names of commands configured in project `.xbase.json`
+    pub commands: Vec<String>,
+    /// How file changes are handled while watching is paused, None if not paused
+    pub paused: Option<PauseMode>,
 }
 
 /// Type of operation for building/ruuning a target/scheme
@@ -23,6 +30,16 @@ pub enum Operation {
     Once,
 }
 
+/// How file changes received while watching is paused are handled
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseMode {
+    /// Buffer changes and process them at once when watching is resumed
+    Buffer,
+    /// Drop changes
+    Drop,
+}
+
 /// Build Settings used in building/running a target/scheme
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
 pub struct BuildSettings {
@@ -69,6 +86,12 @@ impl Default for Operation {
     }
 }
 
+impl Default for PauseMode {
+    fn default() -> Self {
+        Self::Buffer
+    }
+}
+
 impl Display for BuildSettings {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "-configuration {}", self.configuration)?;
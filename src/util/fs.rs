@@ -1,7 +1,11 @@
 //! Functions to query/access filesystem
 use crate::BuildSettings;
 use anyhow::Result;
-use std::{fmt::Debug, path::Path};
+use std::{
+    ffi::OsString,
+    fmt::Debug,
+    path::{Path, PathBuf},
+};
 use tap::Pipe;
 use tokio::fs;
 
@@ -15,8 +19,72 @@ pub fn get_dirname_dir_root(path: impl AsRef<Path>) -> Option<String> {
         .pipe(Some)
 }
 
+/// Environment variable to override the directory holding daemon runtime files
+pub const RUNTIME_DIR_ENV: &str = "XBASE_RUNTIME_DIR";
+
+/// Environment variable to override daemon socket address
+pub const SOCK_ADDR_ENV: &str = "XBASE_SOCK_ADDR";
+
+/// Get per-user directory holding daemon socket, pid and logs.
+///
+/// Resolved from `XBASE_RUNTIME_DIR`, then `$XDG_RUNTIME_DIR/xbase`, then
+/// `$TMPDIR/xbase-<uid>` falling back to `/tmp` when `TMPDIR` isn't set.
+pub fn runtime_dir() -> PathBuf {
+    let uid = unsafe { libc::getuid() };
+    resolve_runtime_dir(|name| std::env::var_os(name), uid)
+}
+
+/// Resolve runtime directory from a given environment lookup, see [`runtime_dir`]
+fn resolve_runtime_dir<F>(env: F, uid: u32) -> PathBuf
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let var = |name| env(name).filter(|v| !v.is_empty());
+
+    if let Some(dir) = var(RUNTIME_DIR_ENV) {
+        return dir.into();
+    }
+
+    if let Some(dir) = var("XDG_RUNTIME_DIR") {
+        return PathBuf::from(dir).join("xbase");
+    }
+
+    var("TMPDIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| "/tmp".into())
+        .join(format!("xbase-{uid}"))
+}
+
+/// Create runtime directory if it doesn't exist, accessible only by current user.
+///
+/// Fails if an existing path isn't a directory owned by current user, since another user could
+/// otherwise take over the daemon socket in a shared directory like `/tmp`.
+pub async fn ensure_runtime_dir(path: impl AsRef<Path>) -> Result<()> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    let path = path.as_ref();
+    let metadata = match fs::symlink_metadata(path).await {
+        Ok(metadata) => metadata,
+        Err(_) => {
+            fs::create_dir_all(path).await?;
+            fs::metadata(path).await?
+        }
+    };
+
+    let uid = unsafe { libc::getuid() };
+    if !metadata.is_dir() {
+        anyhow::bail!("Runtime directory {path:?} is not a directory");
+    }
+    if metadata.uid() != uid {
+        anyhow::bail!("Runtime directory {path:?} is not owned by current user ({uid})");
+    }
+    if metadata.mode() & 0o777 != 0o700 {
+        fs::set_permissions(path, std::fs::Permissions::from_mode(0o700)).await?;
+    }
+    Ok(())
+}
+
 /// Ensure single socket server and process running
-pub async fn cleanup_daemon_runtime(pid_path: &'static str, sock_addr: &'static str) -> Result<()> {
+pub async fn cleanup_daemon_runtime(pid_path: &str, sock_addr: &str) -> Result<()> {
     if fs::metadata(sock_addr).await.ok().is_some() {
         fs::remove_file(sock_addr).await.ok();
         if fs::metadata(pid_path).await.ok().is_some() {
@@ -70,62 +138,96 @@ pub fn which(cmd: &str) -> Result<String> {
     Ok(which::which(cmd)?.to_str().unwrap().to_string())
 }
 
-/// Read .gitignore from root and return vec of glob patterns if the .gitignore eixists.
-pub async fn gitignore_to_glob_patterns<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
-    let gitignore_path = path.as_ref().join(".gitignore");
-    if !gitignore_path.exists() {
-        return Ok(Default::default());
+/// Temporary directory for tests, created empty and removed on drop
+#[cfg(test)]
+pub struct TempRoot(PathBuf);
+
+#[cfg(test)]
+impl TempRoot {
+    /// Create an empty directory with a given name under system temp directory
+    pub fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(name);
+        std::fs::remove_dir_all(&root).ok();
+        std::fs::create_dir_all(&root).unwrap();
+        Self(root)
     }
-    let content = tokio::fs::read_to_string(gitignore_path).await?;
-    Ok(gitignore_content_to_glob_patterns(content))
 }
 
-pub fn gitignore_content_to_glob_patterns(content: String) -> Vec<String> {
-    content
-        .split("\n")
-        .filter(|s| !s.is_empty() && !s.starts_with("#"))
-        .flat_map(|s| {
-            if s.starts_with("!") {
-                None // TODO(watchignore): support ! patterns
-            } else {
-                Some(("", s))
-            }
-        })
-        .filter(|&(_, s)| s.chars().next() != Some('/'))
-        .map(|(pat, s)| {
-            if s != "/" && !s.starts_with("**") {
-                (pat, format!("**/{s}"))
-            } else {
-                (pat, format!("{s}"))
-            }
-        })
-        .flat_map(|(pat, s)| {
-            let pattern = format!("{pat}{s}");
-            vec![
-                pattern.clone(),
-                if pattern.ends_with("/") {
-                    format!("{pattern}**")
-                } else {
-                    format!("{pattern}/**")
-                },
-            ]
-        })
-        .collect::<Vec<String>>()
+#[cfg(test)]
+impl std::ops::Deref for TempRoot {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl AsRef<Path> for TempRoot {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempRoot {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.0).ok();
+    }
 }
 
 #[test]
-fn test_gitignore_patterns() {
-    let gitignore_patterns =
-        gitignore_content_to_glob_patterns(String::from(".build.log\n.compile"));
+fn test_runtime_dir_override() {
+    let env = |vars: &'static [(&'static str, &'static str)]| {
+        move |name: &str| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| OsString::from(value))
+        }
+    };
+
+    let vars = &[(RUNTIME_DIR_ENV, "/tmp/xbase-test"), ("TMPDIR", "/var/tmp")];
+    assert_eq!(
+        resolve_runtime_dir(env(vars), 501),
+        PathBuf::from("/tmp/xbase-test")
+    );
+
+    let vars = &[(RUNTIME_DIR_ENV, ""), ("XDG_RUNTIME_DIR", "/run/user/501")];
     assert_eq!(
-        gitignore_patterns,
-        vec![
-            "**/.build.log".to_string(),
-            "**/.build.log/**".to_string(),
-            "**/.compile".to_string(),
-            "**/.compile/**".to_string()
-        ]
+        resolve_runtime_dir(env(vars), 501),
+        PathBuf::from("/run/user/501/xbase")
     );
 
-    println!("{gitignore_patterns:#?}");
+    let vars = &[("TMPDIR", "/var/tmp")];
+    assert_eq!(
+        resolve_runtime_dir(env(vars), 501),
+        PathBuf::from("/var/tmp/xbase-501")
+    );
+    assert_eq!(
+        resolve_runtime_dir(env(&[]), 501),
+        PathBuf::from("/tmp/xbase-501")
+    );
+}
+
+#[tokio::test]
+async fn test_ensure_runtime_dir() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let root = std::env::temp_dir().join("xbase-test-ensure-runtime-dir");
+    std::fs::remove_dir_all(&root).ok();
+
+    let dir = root.join("xbase");
+    ensure_runtime_dir(&dir).await.unwrap();
+    let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(&dir), 0o700);
+
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+    ensure_runtime_dir(&dir).await.unwrap();
+    assert_eq!(mode(&dir), 0o700);
+
+    let file = root.join("file");
+    std::fs::write(&file, "").unwrap();
+    assert!(ensure_runtime_dir(&file).await.is_err());
+
+    std::fs::remove_dir_all(&root).ok();
 }
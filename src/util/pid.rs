@@ -14,7 +14,7 @@ pub async fn kill_process_by_pid(pid_str: &String) -> anyhow::Result<bool> {
 /// Check if a process exists with a given pid
 #[allow(dead_code)]
 pub fn is_valid_pid(pid: &i32, cb: impl FnOnce()) -> bool {
-    if libproc::libproc::proc_pid::name(*pid).is_err() {
+    if platform::process_name(*pid).is_none() {
         cb();
         false
     } else {
@@ -33,13 +33,8 @@ where
     S: AsRef<OsStr> + Display,
     String: PartialEq<S>,
 {
-    use libproc::libproc::proc_pid;
-
-    let pids = proc_pid::listpids(proc_pid::ProcType::ProcAllPIDS)?;
-
-    for pid in pids {
-        let pid = pid as i32;
-        match proc_pid::name(pid).ok() {
+    for pid in platform::list_pids()? {
+        match platform::process_name(pid) {
             Some(process) if process.eq(&name) => return Ok(pid),
             _ => continue,
         }
@@ -48,9 +43,56 @@ where
     Err(crate::Error::Lookup("Process".into(), format!("{name}")))
 }
 
+/// Process lookup using libproc
+#[cfg(target_os = "macos")]
+mod platform {
+    use libproc::libproc::proc_pid;
+
+    /// Get all running processes pids
+    pub fn list_pids() -> crate::Result<Vec<i32>> {
+        let pids = proc_pid::listpids(proc_pid::ProcType::ProcAllPIDS)?;
+        Ok(pids.into_iter().map(|pid| pid as i32).collect())
+    }
+
+    /// Get process name by pid
+    pub fn process_name(pid: i32) -> Option<String> {
+        proc_pid::name(pid).ok()
+    }
+}
+
+/// Process lookup using procfs
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use std::os::unix::ffi::OsStrExt;
+
+    /// Get all running processes pids
+    pub fn list_pids() -> crate::Result<Vec<i32>> {
+        Ok(std::fs::read_dir("/proc")?
+            .flatten()
+            .flat_map(|entry| entry.file_name().to_str()?.parse::<i32>().ok())
+            .collect())
+    }
+
+    /// Get process name by pid, from executable path in `cmdline` since `comm` is truncated to
+    /// 15 characters. Kernel threads have no `cmdline`, so `comm` is used instead.
+    pub fn process_name(pid: i32) -> Option<String> {
+        let cmdline = std::fs::read(format!("/proc/{pid}/cmdline")).ok()?;
+        let program = cmdline.split(|byte| *byte == 0).next().unwrap_or_default();
+        if program.is_empty() {
+            return std::fs::read_to_string(format!("/proc/{pid}/comm"))
+                .ok()
+                .map(|name| name.trim_end().to_string());
+        }
+
+        let program = std::path::Path::new(std::ffi::OsStr::from_bytes(program));
+        Some(program.file_name()?.to_string_lossy().to_string())
+    }
+}
+
 #[test]
 fn test_get_by_name() {
-    let existing_process = get_pid_by_name("DockHelper");
+    let name = platform::process_name(std::process::id() as i32).unwrap();
+    let existing_process = get_pid_by_name(name.as_str());
     let not_process = get_pid_by_name("afsd8439f");
 
     assert!(existing_process.is_ok());
@@ -60,12 +102,37 @@ fn test_get_by_name() {
 #[test]
 #[ignore = "internal"]
 fn test_get_os_processes() {
-    use libproc::libproc::proc_pid::*;
-    let pids = listpids(ProcType::ProcAllPIDS).unwrap();
-
-    for pid in pids {
-        if let Some(name) = name(pid as i32).ok() {
+    for pid in platform::list_pids().unwrap() {
+        if let Some(name) = platform::process_name(pid) {
             println!("{name}")
         }
     }
 }
+
+#[test]
+#[cfg(not(target_os = "macos"))]
+fn test_process_name_not_truncated() {
+    let dir = std::env::temp_dir().join("xbase-test-process-name");
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("xbase-long-process-name");
+    std::fs::copy("/bin/sleep", &program).unwrap();
+
+    let mut child = std::process::Command::new(&program)
+        .arg("5")
+        .spawn()
+        .unwrap();
+    // Child cmdline isn't available till the program is executed
+    let expected = Some("xbase-long-process-name");
+    let name = (0..50)
+        .map(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            platform::process_name(child.id() as i32)
+        })
+        .find(|name| name.as_deref() == expected)
+        .flatten();
+    child.kill().ok();
+    child.wait().ok();
+
+    assert_eq!(name.as_deref(), expected);
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use typescript_type_def::TypeDef;
+
+/// Minimum interval between scans of polling backend, shorter intervals are clamped to it
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// File created at project root to probe whether native events are delivered
+pub const PROBE_FILE: &str = ".xbase-watch-probe";
+
+/// File watcher backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TypeDef)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchBackend {
+    /// Platform native events, e.g. inotify or fsevents
+    Native,
+    /// Periodic scan of project files, works on network and container filesystems
+    Poll,
+}
+
+/// File watcher configuration, see [`crate::ProjectConfig`]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WatcherConfig {
+    /// Backend to use, by default native backend is used unless no native events are received
+    /// within [`WatcherConfig::probe_timeout`]
+    pub backend: Option<WatchBackend>,
+    /// Interval between scans of polling backend in milliseconds, see [`MIN_POLL_INTERVAL`]
+    pub poll_interval: u64,
+    /// Time to wait for native events of probe file in milliseconds
+    pub probe_timeout: u64,
+    /// Additional directories to watch, relative to project root
+    pub paths: Vec<PathBuf>,
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            backend: None,
+            poll_interval: 2000,
+            probe_timeout: 3000,
+            paths: vec![],
+        }
+    }
+}
+
+impl WatcherConfig {
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval).max(MIN_POLL_INTERVAL)
+    }
+
+    pub fn probe_timeout(&self) -> Duration {
+        Duration::from_millis(self.probe_timeout)
+    }
+}
+
+#[test]
+fn test_poll_interval_clamped() {
+    let config = |poll_interval| WatcherConfig {
+        poll_interval,
+        ..Default::default()
+    };
+
+    assert_eq!(config(0).poll_interval(), MIN_POLL_INTERVAL);
+    assert_eq!(config(10).poll_interval(), MIN_POLL_INTERVAL);
+    assert_eq!(config(2000).poll_interval(), Duration::from_secs(2));
+}
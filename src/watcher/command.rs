@@ -0,0 +1,270 @@
+use super::{Event, EventBatch, WatchJob, Watchable};
+use crate::{Broadcast, Error, ProjectImpl, Result, Task, TaskKind};
+use async_trait::async_trait;
+use process_stream::Process;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{fmt, time::SystemTime};
+
+/// User defined command ran on file changes, configured in [`crate::ProjectConfig`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandConfig {
+    /// Command name, used as task target and to watch or stop the command
+    pub name: String,
+    /// Shell command template, `{paths}` is replaced with changed paths and `{root}` with
+    /// project root
+    pub command: String,
+    /// Glob patterns, relative to project root, of files that trigger the command. All files
+    /// trigger the command when empty
+    #[serde(default)]
+    pub globs: Vec<String>,
+}
+
+/// Watchable running a [`CommandConfig`] with paths changed within a batch
+pub struct CommandWatchable {
+    key: String,
+    root: PathBuf,
+    config: CommandConfig,
+    /// Last run of the command, see [`CommandRun`]
+    last_run: Arc<Mutex<Option<CommandRun>>>,
+}
+
+/// Paths given to a command run along with its start and end. Given paths modified within are
+/// considered written by the command itself, e.g. by a formatter, so they don't trigger the
+/// command again. Other paths modified while the command runs still trigger it.
+#[derive(Debug, Clone)]
+struct CommandRun {
+    paths: Vec<PathBuf>,
+    start: SystemTime,
+    /// None while the command is still running
+    end: Option<SystemTime>,
+}
+
+impl CommandRun {
+    /// Whether a given path modified at a given time was written by this run
+    fn wrote(&self, path: &Path, modified: SystemTime) -> bool {
+        self.paths.iter().any(|p| p == path)
+            && modified >= self.start
+            && self.end.map_or(true, |end| modified <= end)
+    }
+}
+
+impl CommandWatchable {
+    pub fn new(key: String, root: &Path, config: CommandConfig) -> Result<Self> {
+        wax::any::<wax::Glob, _>(config.globs.iter().map(String::as_str).collect::<Vec<_>>())
+            .map_err(|err| Error::Unexpected(format!("[{}] Invalid glob: {err}", config.name)))?;
+
+        Ok(Self {
+            key,
+            root: root.to_path_buf(),
+            config,
+            last_run: Default::default(),
+        })
+    }
+
+    /// Get paths changed within a batch that match command globs, removed paths are skipped
+    fn changed_paths(&self, batch: &EventBatch) -> Vec<PathBuf> {
+        let globs = self
+            .config
+            .globs
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let Ok(globs) = wax::any::<wax::Glob, _>(globs) else {
+            return vec![];
+        };
+
+        batch
+            .iter()
+            .filter(|event| !event.is_remove_event())
+            .map(Event::path)
+            .filter(|path| {
+                let relative = path.strip_prefix(&self.root).unwrap_or(path);
+                self.config.globs.is_empty()
+                    || wax::Pattern::is_match(&globs, &*relative.to_string_lossy())
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Get changed paths, see [`CommandWatchable::changed_paths`], that weren't written by the
+    /// last run of the command
+    async fn triggering_paths(&self, batch: &EventBatch) -> Vec<PathBuf> {
+        let last_run = self.last_run.lock().unwrap().clone();
+        let Some(last_run) = last_run else {
+            return self.changed_paths(batch);
+        };
+
+        let mut paths = vec![];
+        for path in self.changed_paths(batch) {
+            let modified = tokio::fs::metadata(&path).await.and_then(|m| m.modified());
+            match modified {
+                Ok(modified) if last_run.wrote(&path, modified) => {
+                    tracing::debug!("[{}] Skip {path:?} written by command", self.config.name);
+                }
+                _ => paths.push(path),
+            }
+        }
+        paths
+    }
+
+    /// Get shell command with template placeholders replaced
+    fn render(&self, paths: &[PathBuf]) -> String {
+        let quote = |path: &Path| shell_words::quote(&path.to_string_lossy()).to_string();
+        let paths = paths.iter().map(|p| quote(p)).collect::<Vec<_>>();
+
+        self.config
+            .command
+            .replace("{root}", &quote(&self.root))
+            .replace("{paths}", &paths.join(" "))
+    }
+}
+
+impl fmt::Display for CommandWatchable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.key)
+    }
+}
+
+#[async_trait]
+impl Watchable for CommandWatchable {
+    async fn trigger(
+        &self,
+        _project: &mut ProjectImpl,
+        event: &EventBatch,
+        broadcast: &Arc<Broadcast>,
+    ) -> Result<WatchJob> {
+        let paths = self.triggering_paths(event).await;
+        let command = self.render(&paths);
+        let task = Task::new(TaskKind::Command, &self.config.name, broadcast.clone());
+        let mut process = Process::new("/bin/sh");
+
+        process.args(["-c", &command]);
+        process.current_dir(&self.root);
+        task.debug(format!("[{}] {command}", self.config.name));
+
+        // Start the run before spawning so early writes by the command are within it
+        let last_run = self.last_run.clone();
+        *last_run.lock().unwrap() = Some(CommandRun {
+            paths,
+            start: SystemTime::now(),
+            end: None,
+        });
+        let mut recv = task.consume(Box::new(process)).map_err(|err| {
+            last_run.lock().unwrap().take();
+            err
+        })?;
+
+        Ok(Box::pin(async move {
+            recv.recv().await;
+            if let Some(run) = last_run.lock().unwrap().as_mut() {
+                run.end = Some(SystemTime::now());
+            }
+            Ok(())
+        }))
+    }
+
+    /// Trigger only when changed files match command globs and weren't written by the command
+    async fn should_trigger(&self, _project: &ProjectImpl, event: &EventBatch) -> bool {
+        !self.triggering_paths(event).await.is_empty()
+    }
+
+    async fn should_discard(&self, _event: &EventBatch) -> bool {
+        false
+    }
+
+    async fn discard(&self) {}
+}
+
+#[test]
+fn test_command_watchable_paths() {
+    use crate::util::fs::TempRoot;
+    use notify::event::{CreateKind, EventKind as NotifyEventKind, RemoveKind};
+
+    let root = TempRoot::new("xbase-test-command");
+    let config: CommandConfig = serde_json::from_value(serde_json::json!({
+        "name": "lint",
+        "command": "swiftlint lint --path {root} {paths}",
+        "globs": ["Sources/**/*.swift"],
+    }))
+    .unwrap();
+    let command = CommandWatchable::new("lint".into(), &root, config).unwrap();
+
+    let mut batch = EventBatch::from_paths(
+        &root,
+        NotifyEventKind::Create(CreateKind::File),
+        &["Sources/App/My View.swift", "Resources/Info.plist"],
+    );
+    batch.append(EventBatch::from_paths(
+        &root,
+        NotifyEventKind::Remove(RemoveKind::File),
+        &["Sources/App/Old.swift"],
+    ));
+
+    let paths = command.changed_paths(&batch);
+    assert_eq!(paths, vec![root.join("Sources/App/My View.swift")]);
+    assert_eq!(
+        command.render(&paths),
+        format!(
+            "swiftlint lint --path {} '{}'",
+            root.display(),
+            root.join("Sources/App/My View.swift").display()
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_command_watchable_skips_own_writes() {
+    use crate::util::fs::TempRoot;
+    use notify::event::{DataChange, EventKind as NotifyEventKind, ModifyKind};
+    use std::time::Duration;
+
+    let root = TempRoot::new("xbase-test-command-writes");
+    std::fs::create_dir_all(root.join("Sources")).unwrap();
+    let config: CommandConfig = serde_json::from_value(serde_json::json!({
+        "name": "format",
+        "command": "swiftformat {paths}",
+        "globs": ["Sources/*.swift"],
+    }))
+    .unwrap();
+    let command = CommandWatchable::new("format".into(), &root, config).unwrap();
+
+    let kind = NotifyEventKind::Modify(ModifyKind::Data(DataChange::Any));
+    let batch = |path: &str| EventBatch::from_paths(&root, kind.clone(), &[path]);
+    let run = |start, end| {
+        *command.last_run.lock().unwrap() = Some(CommandRun {
+            paths: vec![root.join("Sources/A.swift")],
+            start,
+            end,
+        });
+    };
+
+    // Given to the command and written while it was running
+    let start = SystemTime::now() - Duration::from_secs(1);
+    std::fs::write(root.join("Sources/A.swift"), "").unwrap();
+    std::fs::write(root.join("Sources/B.swift"), "").unwrap();
+    run(start, None);
+    assert!(command
+        .triggering_paths(&batch("Sources/A.swift"))
+        .await
+        .is_empty());
+    run(start, Some(SystemTime::now()));
+    assert!(command
+        .triggering_paths(&batch("Sources/A.swift"))
+        .await
+        .is_empty());
+
+    // Not given to the command, e.g. saved by the user while the command was running
+    assert_eq!(
+        command.triggering_paths(&batch("Sources/B.swift")).await,
+        vec![root.join("Sources/B.swift")]
+    );
+
+    // Written after the command finished
+    run(start - Duration::from_secs(1), Some(start));
+    assert_eq!(
+        command.triggering_paths(&batch("Sources/A.swift")).await,
+        vec![root.join("Sources/A.swift")]
+    );
+}
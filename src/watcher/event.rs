@@ -1,10 +1,10 @@
 #![allow(dead_code)]
-use super::WatcherState;
+use super::IgnoreFiles;
 use notify::{Event as NotifyEvent, EventKind as NotifyEventKind};
 use std::{
+    collections::{HashMap, HashSet},
     fmt,
-    path::PathBuf,
-    sync::{Arc, Mutex},
+    path::{Path, PathBuf},
 };
 use wax::Any;
 
@@ -13,7 +13,14 @@ pub struct Event {
     path: PathBuf,
     file_name: String,
     kind: EventKind,
-    last_path: Arc<Mutex<PathBuf>>,
+}
+
+/// Events received within a debounce window, deduplicated by path
+#[derive(Default, Debug)]
+pub struct EventBatch {
+    events: Vec<Event>,
+    /// Index of events by path
+    paths: HashMap<PathBuf, usize>,
 }
 
 #[derive(Debug)]
@@ -23,8 +30,12 @@ pub enum EventKind {
     FolderCreated,
     FolderRemoved,
     FileUpdated,
-    FileRenamed,
     FileRemoved,
+    /// File or folder moved within watched root
+    Renamed {
+        from: PathBuf,
+        to: PathBuf,
+    },
     Other(NotifyEventKind),
 }
 
@@ -37,23 +48,72 @@ impl Default for EventKind {
 impl Event {
     pub fn new<'a>(
         ignore: &'a Any<'a>,
-        state: &WatcherState,
+        ignore_files: &IgnoreFiles,
         mut event: NotifyEvent,
     ) -> Option<Self> {
-        use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind};
+        use notify::event::{CreateKind, ModifyKind, RemoveKind, RenameMode};
         use NotifyEventKind::*;
 
-        if event.paths.len() > 1 {
+        if event.paths.is_empty() {
+            tracing::trace!("Skip {:?} without paths", event.kind);
+            return None;
+        }
+
+        let is_ignored = |path: &PathBuf| {
+            wax::Pattern::is_match(ignore, &*path.to_string_lossy())
+                || ignore_files.is_ignored(path, path.is_dir())
+        };
+
+        let is_rename = matches!(event.kind, Modify(ModifyKind::Name(RenameMode::Both)));
+        if event.paths.len() > 1 && !is_rename {
             tracing::error!("More than one path! {:#?}", event)
         }
 
         let kind = match event.kind {
+            Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                let to = event.paths.pop().unwrap();
+                let from = event.paths.pop().unwrap();
+                // Moving from or into an ignored path is a creation or a removal
+                match (is_ignored(&from), is_ignored(&to)) {
+                    (true, true) => {
+                        tracing::trace!("{from:?} -> {to:?} ignored");
+                        return None;
+                    }
+                    (true, false) => {
+                        event.paths.push(to);
+                        Self::created(&event.paths[0])
+                    }
+                    (false, true) => {
+                        event.paths.push(from);
+                        if to.is_dir() {
+                            EventKind::FolderRemoved
+                        } else {
+                            EventKind::FileRemoved
+                        }
+                    }
+                    (false, false) => {
+                        event.paths.push(to.clone());
+                        EventKind::Renamed { from, to }
+                    }
+                }
+            }
             Create(CreateKind::File) => EventKind::FileCreated,
             Create(CreateKind::Folder) => EventKind::FolderCreated,
-            Modify(ModifyKind::Data(DataChange::Content)) => EventKind::FileUpdated,
-            Modify(ModifyKind::Name(_)) => EventKind::FileRenamed,
-            Remove(RemoveKind::File) => EventKind::FileRemoved,
+            Create(_) => Self::created(&event.paths[0]),
+            Modify(ModifyKind::Data(_) | ModifyKind::Any) if event.paths[0].is_file() => {
+                EventKind::FileUpdated
+            }
+            // Only one side of a rename is known, so it is either a creation or a removal
+            Modify(ModifyKind::Name(_)) if event.paths[0].exists() => {
+                Self::created(&event.paths[0])
+            }
+            Modify(ModifyKind::Name(_)) => EventKind::FileRemoved,
             Remove(RemoveKind::Folder) => EventKind::FolderRemoved,
+            // Removed paths can't be checked whether they were folders, see [`KnownDirs::update`]
+            Remove(_) => EventKind::FileRemoved,
+            // Backends unable to tell what changed
+            NotifyEventKind::Any if event.paths[0].is_file() => EventKind::FileUpdated,
+            NotifyEventKind::Any if !event.paths[0].exists() => EventKind::FileRemoved,
             kind => EventKind::Other(kind),
         };
 
@@ -66,10 +126,8 @@ impl Event {
             }
         };
 
-        let is_match = wax::Pattern::is_match;
-
-        // Skip ignore paths
-        if is_match(ignore, &*path.to_string_lossy()) {
+        // Skip ignore paths, renames are already checked above
+        if !matches!(kind, EventKind::Renamed { .. }) && is_ignored(&path) {
             tracing::trace!(r#""{file_name}" ignored"#);
             return None;
         }
@@ -80,21 +138,20 @@ impl Event {
             return None;
         }
 
-        let event = Self {
+        Some(Self {
             path,
             file_name,
             kind,
-            last_path: state.last_path(),
-        };
+        })
+    }
 
-        // Skip when last run was less then 1 second agot
-        let last_run = state.last_run();
-        if !(last_run > 1) {
-            tracing::trace!("Skip [last_run: {last_run}] [{event}]");
-            return None;
+    /// Get creation event kind of an existing path
+    fn created(path: &PathBuf) -> EventKind {
+        if path.is_dir() {
+            EventKind::FolderCreated
+        } else {
+            EventKind::FileCreated
         }
-
-        Some(event)
     }
 
     /// Returns `true` if the watch event kind is [`EventKind::FileUpdated`]
@@ -102,14 +159,6 @@ impl Event {
         matches!(self.kind, EventKind::FileUpdated)
     }
 
-    pub fn is_any_but_not_seen(&self) -> bool {
-        self.is_content_update_event()
-            || self.is_rename_event()
-            || self.is_create_event()
-            || self.is_remove_event()
-            || !(self.path().exists() || self.is_seen())
-    }
-
     /// Returns `true` if the watch event kind is [`EventKind::FileCreated`] or
     /// [`EventKind::FolderCretaed`].
     pub fn is_create_event(&self) -> bool {
@@ -119,12 +168,12 @@ impl Event {
     /// Returns `true` if the watch event kind is [`EventKind::FileRemoved`] or
     /// [`EventKind::FolderRemoved`].
     pub fn is_remove_event(&self) -> bool {
-        matches!(self.kind, EventKind::FileRemoved) || matches!(self.kind, EventKind::FolderCreated)
+        matches!(self.kind, EventKind::FileRemoved) || matches!(self.kind, EventKind::FolderRemoved)
     }
 
-    /// Returns `true` if the watch event kind is [`EventKind::FileRenamed`].
+    /// Returns `true` if the watch event kind is [`EventKind::Renamed`].
     pub fn is_rename_event(&self) -> bool {
-        matches!(self.kind, EventKind::FileRenamed)
+        matches!(self.kind, EventKind::Renamed { .. })
     }
 
     /// Returns `true` if the watch event kind is [`EventKind::Other`].
@@ -150,30 +199,131 @@ impl Event {
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
+}
 
-    /// Get the event's is seen.
-    #[must_use]
-    pub fn is_seen(&self) -> bool {
-        tracing::trace!("{}", self.file_name);
+impl EventBatch {
+    /// Add an event to the batch, replacing previous event of the same path.
+    ///
+    /// Updates of a file created within the same batch are still considered creation.
+    pub fn push(&mut self, event: Event) {
+        match self.paths.get(event.path()) {
+            Some(&idx) if self.events[idx].is_create_event() && event.is_content_update_event() => {
+            }
+            Some(&idx) => self.events[idx] = event,
+            None => {
+                self.paths.insert(event.path().clone(), self.events.len());
+                self.events.push(event);
+            }
+        }
+    }
+
+    /// Add events of another batch, see [`EventBatch::push`]
+    pub fn append(&mut self, batch: EventBatch) {
+        batch.events.into_iter().for_each(|event| self.push(event));
+    }
+
+    /// Iterate over batch events in the order they were first received
+    pub fn iter(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
 
-        if self.file_name.eq("project.yml") {
-            return false;
+    /// Get path of the first changed file, None for batches not created from file changes
+    pub fn changed_path(&self) -> Option<&PathBuf> {
+        self.events.first().map(Event::path)
+    }
+}
+
+#[cfg(test)]
+impl EventBatch {
+    /// Create a batch of notify events of a given kind for paths relative to a given root
+    pub fn from_paths(root: &Path, kind: NotifyEventKind, paths: &[&str]) -> Self {
+        let ignore = wax::any::<wax::Glob, _>(vec!["**/.git/**"]).unwrap();
+        let ignore_files = IgnoreFiles::new(root);
+        let mut batch = Self::default();
+        for path in paths {
+            let event = NotifyEvent::new(kind.clone()).add_path(root.join(path));
+            batch.push(Event::new(&ignore, &ignore_files, event).unwrap());
         }
-        let mut last_path = match self.last_path.lock() {
-            Ok(path) => path,
-            Err(err) => {
-                tracing::error!("{err}");
-                err.into_inner()
-            }
+        batch
+    }
+}
+
+/// Directories under watched roots, used to tell removed folders apart from removed files
+#[derive(Default, Debug)]
+pub struct KnownDirs(HashSet<PathBuf>);
+
+impl KnownDirs {
+    /// Collect directories of given roots, skipping ignored directories
+    pub fn new<'a, F>(roots: impl Iterator<Item = &'a PathBuf>, is_ignored: F) -> Self
+    where
+        F: Fn(&Path) -> bool,
+    {
+        let mut dirs = Self::default();
+        roots.for_each(|root| dirs.insert_all(root, &is_ignored));
+        dirs
+    }
+
+    /// Collect directories of a root watched after creation
+    pub fn insert_root<F: Fn(&Path) -> bool>(&mut self, root: &Path, is_ignored: F) {
+        self.insert_all(root, &is_ignored)
+    }
+
+    /// Forget directories of a root no longer watched
+    pub fn remove_root(&mut self, root: &Path) {
+        self.remove_all(root);
+    }
+
+    fn insert_all<F: Fn(&Path) -> bool>(&mut self, dir: &Path, is_ignored: &F) {
+        self.0.insert(dir.to_path_buf());
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
         };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_dir = entry.file_type().map_or(false, |t| t.is_dir());
+            if is_dir && entry.file_name() != ".git" && !is_ignored(&path) {
+                self.insert_all(&path, is_ignored);
+            }
+        }
+    }
 
-        if last_path.eq(self.path()) {
-            true
-        } else {
-            *last_path = self.path.clone();
-            false
+    fn remove_all(&mut self, dir: &Path) -> bool {
+        let known = self.0.contains(dir);
+        self.0.retain(|path| !path.starts_with(dir));
+        known
+    }
+
+    /// Track directories changed by a given event, removal of a known directory is updated to
+    /// [`EventKind::FolderRemoved`].
+    pub fn update(&mut self, event: &mut Event) {
+        match &event.kind {
+            EventKind::FolderCreated => self.insert_all(&event.path, &|_| false),
+            EventKind::FileRemoved | EventKind::FolderRemoved => {
+                if self.remove_all(&event.path) {
+                    event.kind = EventKind::FolderRemoved;
+                }
+            }
+            EventKind::Renamed { from, to } => {
+                self.remove_all(from);
+                if to.is_dir() {
+                    self.insert_all(to, &|_| false);
+                }
+            }
+            _ => {}
         }
     }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.0.contains(path)
+    }
 }
 
 impl fmt::Display for Event {
@@ -183,7 +333,10 @@ impl fmt::Display for Event {
             FolderCreated | FileCreated => "created",
             FolderRemoved | FileRemoved => "removed",
             FileUpdated => "modified",
-            FileRenamed => "renamed",
+            Renamed { from, .. } => {
+                let from = from.file_name().unwrap_or_default().to_string_lossy();
+                return write!(f, "{from:?} -> {:?} [renamed]", self.file_name);
+            }
             Other(event) => {
                 tracing::trace!("{:?}", event);
                 "other"
@@ -193,3 +346,214 @@ impl fmt::Display for Event {
         write!(f, "{:?} [{event_name}]", self.file_name)
     }
 }
+
+impl fmt::Display for EventBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.events.as_slice() {
+            [event] => write!(f, "{event}"),
+            [event, rest @ ..] => write!(f, "{event} and {} more", rest.len()),
+            [] => write!(f, "no events"),
+        }
+    }
+}
+
+#[test]
+fn test_event_batch_dedupe_by_path() {
+    let event = |path: &str, kind| Event {
+        path: path.into(),
+        file_name: path.rsplit('/').next().unwrap().into(),
+        kind,
+    };
+    let mut batch = EventBatch::default();
+
+    batch.push(event("/root/Sources/A.swift", EventKind::FileCreated));
+    batch.push(event("/root/Sources/B.swift", EventKind::FileUpdated));
+    batch.push(event("/root/Sources/A.swift", EventKind::FileUpdated));
+    batch.push(event("/root/Sources/B.swift", EventKind::FileRemoved));
+    batch.push(event("/root/Sources/B.swift", EventKind::FileUpdated));
+
+    assert_eq!(batch.len(), 2);
+    let events = batch.iter().collect::<Vec<_>>();
+    assert!(events[0].is_create_event());
+    assert!(events[1].is_content_update_event());
+    assert_eq!(
+        batch.changed_path(),
+        Some(&PathBuf::from("/root/Sources/A.swift"))
+    );
+
+    let mut paused = EventBatch::default();
+    paused.push(event("/root/Sources/C.swift", EventKind::FileCreated));
+    paused.push(event("/root/Sources/A.swift", EventKind::FileRemoved));
+    batch.append(paused);
+
+    assert_eq!(batch.len(), 3);
+    assert!(batch.iter().next().unwrap().is_remove_event());
+}
+
+#[test]
+fn test_event_kind_from_notify_event() {
+    use crate::util::fs::TempRoot;
+    use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind, RenameMode};
+    use std::fs::{create_dir_all, write};
+    use NotifyEventKind::*;
+
+    let root = TempRoot::new("xbase-test-event-kind");
+    create_dir_all(root.join("Sources")).unwrap();
+    create_dir_all(root.join(".git")).unwrap();
+    write(root.join("Sources/A.swift"), "").unwrap();
+    write(root.join(".gitignore"), "/Output\n").unwrap();
+
+    let ignore = wax::any::<wax::Glob, _>(vec!["**/.git/**"]).unwrap();
+    let ignore_files = IgnoreFiles::new(&root);
+    let event = |kind, paths: &[&str]| {
+        let event = paths.iter().fold(NotifyEvent::new(kind), |event, path| {
+            event.add_path(root.join(path))
+        });
+        Event::new(&ignore, &ignore_files, event)
+    };
+    let kind = |kind, paths: &[&str]| event(kind, paths).map(|e| e.kind);
+
+    assert!(matches!(
+        kind(Create(CreateKind::File), &["Sources/A.swift"]),
+        Some(EventKind::FileCreated)
+    ));
+    assert!(matches!(
+        kind(Create(CreateKind::Any), &["Sources"]),
+        Some(EventKind::FolderCreated)
+    ));
+    assert!(matches!(
+        kind(
+            Modify(ModifyKind::Data(DataChange::Any)),
+            &["Sources/A.swift"]
+        ),
+        Some(EventKind::FileUpdated)
+    ));
+    assert!(kind(Modify(ModifyKind::Any), &["Sources"]).is_none());
+    assert!(matches!(
+        kind(Remove(RemoveKind::Folder), &["Sources/Old"]),
+        Some(EventKind::FolderRemoved)
+    ));
+    assert!(matches!(
+        kind(Remove(RemoveKind::Any), &["Sources/B.swift"]),
+        Some(EventKind::FileRemoved)
+    ));
+
+    // Single path renames
+    assert!(matches!(
+        kind(Modify(ModifyKind::Name(RenameMode::To)), &["Sources"]),
+        Some(EventKind::FolderCreated)
+    ));
+    assert!(matches!(
+        kind(
+            Modify(ModifyKind::Name(RenameMode::From)),
+            &["Sources/B.swift"]
+        ),
+        Some(EventKind::FileRemoved)
+    ));
+
+    // Both paths renames
+    let both = Modify(ModifyKind::Name(RenameMode::Both));
+    let renamed = event(both.clone(), &["Sources/B.swift", "Sources/A.swift"]).unwrap();
+    assert!(renamed.is_rename_event());
+    assert_eq!(renamed.path(), &root.join("Sources/A.swift"));
+    assert!(matches!(
+        renamed.kind(),
+        EventKind::Renamed { from, .. } if from == &root.join("Sources/B.swift")
+    ));
+    assert!(matches!(
+        kind(both.clone(), &["Output/A.swift", "Sources/A.swift"]),
+        Some(EventKind::FileCreated)
+    ));
+    let moved_out = event(both.clone(), &["Sources/A.swift", "Output/A.swift"]).unwrap();
+    assert!(moved_out.is_remove_event());
+    assert_eq!(moved_out.path(), &root.join("Sources/A.swift"));
+    create_dir_all(root.join("Output/Old")).unwrap();
+    assert!(matches!(
+        kind(both.clone(), &["Sources/Old", "Output/Old"]),
+        Some(EventKind::FolderRemoved)
+    ));
+    assert!(kind(both, &["Output/A.swift", "Output/B.swift"]).is_none());
+
+    // Unknown kind of changes
+    assert!(matches!(
+        kind(NotifyEventKind::Any, &["Sources/A.swift"]),
+        Some(EventKind::FileUpdated)
+    ));
+    assert!(matches!(
+        kind(NotifyEventKind::Any, &["Sources/B.swift"]),
+        Some(EventKind::FileRemoved)
+    ));
+    assert!(kind(NotifyEventKind::Any, &["Sources"]).is_none());
+
+    // Ignored and path less events
+    assert!(kind(Create(CreateKind::File), &[".git/index"]).is_none());
+    assert!(kind(NotifyEventKind::Any, &[]).is_none());
+}
+
+#[test]
+fn test_known_dirs() {
+    use crate::util::fs::TempRoot;
+    use notify::event::{CreateKind, ModifyKind, RemoveKind, RenameMode};
+    use std::fs::{create_dir_all, remove_dir_all, rename};
+    use NotifyEventKind::*;
+
+    let root = TempRoot::new("xbase-test-known-dirs");
+    create_dir_all(root.join("Sources/Models")).unwrap();
+    create_dir_all(root.join("Output/Cache")).unwrap();
+
+    let ignore = wax::any::<wax::Glob, _>(vec!["**/Output/**"]).unwrap();
+    let ignore_files = IgnoreFiles::new(&root);
+    let roots = vec![root.to_path_buf()];
+    let mut dirs = KnownDirs::new(roots.iter(), |path| path.ends_with("Output"));
+    assert!(dirs.contains(&root.join("Sources/Models")));
+    assert!(!dirs.contains(&root.join("Output")));
+
+    let mut event = |kind, paths: &[&str]| {
+        let event = paths.iter().fold(NotifyEvent::new(kind), |event, path| {
+            event.add_path(root.join(path))
+        });
+        let mut event = Event::new(&ignore, &ignore_files, event)?;
+        dirs.update(&mut event);
+        Some(event.kind)
+    };
+
+    create_dir_all(root.join("Sources/Views/Cells")).unwrap();
+    assert!(matches!(
+        event(Create(CreateKind::Folder), &["Sources/Views"]),
+        Some(EventKind::FolderCreated)
+    ));
+
+    rename(root.join("Sources/Views"), root.join("Sources/Screens")).unwrap();
+    let both = Modify(ModifyKind::Name(RenameMode::Both));
+    assert!(matches!(
+        event(both, &["Sources/Views", "Sources/Screens"]),
+        Some(EventKind::Renamed { .. })
+    ));
+
+    remove_dir_all(root.join("Sources/Screens")).unwrap();
+    assert!(matches!(
+        event(Remove(RemoveKind::Any), &["Sources/Screens/Cells"]),
+        Some(EventKind::FolderRemoved)
+    ));
+    assert!(matches!(
+        event(Remove(RemoveKind::Any), &["Sources/Screens"]),
+        Some(EventKind::FolderRemoved)
+    ));
+    assert!(matches!(
+        event(Remove(RemoveKind::Any), &["Sources/Views"]),
+        Some(EventKind::FileRemoved)
+    ));
+    assert!(matches!(
+        event(Remove(RemoveKind::Any), &["Sources/A.swift"]),
+        Some(EventKind::FileRemoved)
+    ));
+    assert!(dirs.contains(&root.join("Sources/Models")));
+
+    let package = TempRoot::new("xbase-test-known-dirs-package");
+    create_dir_all(package.join("Sources/Core")).unwrap();
+    dirs.insert_root(&package, |_| false);
+    assert!(dirs.contains(&package.join("Sources/Core")));
+    dirs.remove_root(&package);
+    assert!(!dirs.contains(&package.join("Sources/Core")));
+    assert!(dirs.contains(&root.join("Sources/Models")));
+}
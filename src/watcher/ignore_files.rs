@@ -0,0 +1,156 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{Match, WalkBuilder};
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+
+/// Project specific ignore file, using gitignore syntax
+pub const XBASE_IGNORE: &str = ".xbaseignore";
+
+/// Gitignore matchers of a project root.
+///
+/// Covers nested `.gitignore` files, `.git/info/exclude`, git global excludes file and
+/// [`XBASE_IGNORE`] at project root.
+#[derive(Debug)]
+pub struct IgnoreFiles {
+    root: PathBuf,
+    /// Matchers ordered from deepest directory to project root
+    matchers: Vec<Gitignore>,
+    /// Matcher of git global excludes file
+    global: Gitignore,
+}
+
+impl IgnoreFiles {
+    pub fn new(root: &Path) -> Self {
+        let mut matchers = gitignore_dirs(root)
+            .into_iter()
+            .filter_map(|dir| {
+                let mut builder = GitignoreBuilder::new(&dir);
+                let mut files = vec![dir.join(".gitignore")];
+                if dir == root {
+                    // Later files take precedence
+                    files.insert(0, root.join(".git").join("info").join("exclude"));
+                    files.push(root.join(XBASE_IGNORE));
+                }
+
+                for file in files.into_iter().filter(|f| f.exists()) {
+                    if let Some(err) = builder.add(&file) {
+                        tracing::warn!("Failed to parse {file:?}: {err}");
+                    }
+                }
+
+                builder
+                    .build()
+                    .map_err(|err| tracing::warn!("Failed to build {dir:?} ignore: {err}"))
+                    .ok()
+                    .filter(|matcher| !matcher.is_empty())
+            })
+            .collect::<Vec<_>>();
+        matchers.sort_by_key(|m| Reverse(m.path().components().count()));
+
+        let (global, err) = Gitignore::global();
+        if let Some(err) = err {
+            tracing::warn!("Failed to read git global excludes: {err}");
+        }
+
+        Self {
+            root: root.to_path_buf(),
+            matchers,
+            global,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Get index of ignore files with the deepest root containing a given path
+    pub fn position(sets: &[Self], path: &Path) -> Option<usize> {
+        sets.iter()
+            .enumerate()
+            .filter(|(_, set)| path.starts_with(&set.root))
+            .max_by_key(|(_, set)| set.root.components().count())
+            .map(|(idx, _)| idx)
+    }
+
+    /// Whether a given path is ignored, the deepest ignore file with a matching pattern wins
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+
+        for matcher in self.matchers.iter().filter(|m| path.starts_with(m.path())) {
+            match matcher.matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+
+        self.global
+            .matched_path_or_any_parents(relative, is_dir)
+            .is_ignore()
+    }
+
+    /// Whether a given path is an ignore file, so matchers need to be reloaded on its changes
+    pub fn is_ignore_file(path: &Path) -> bool {
+        let name = path.file_name().and_then(|n| n.to_str());
+        matches!(name, Some(".gitignore" | XBASE_IGNORE)) || path.ends_with(".git/info/exclude")
+    }
+}
+
+/// Get project root and nested directories that have a `.gitignore`, skipping ignored directories
+fn gitignore_dirs(root: &Path) -> Vec<PathBuf> {
+    let nested = WalkBuilder::new(root)
+        .hidden(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_name() == ".gitignore")
+        .filter_map(|entry| entry.path().parent().map(Path::to_path_buf))
+        .filter(|dir| dir != root);
+
+    std::iter::once(root.to_path_buf()).chain(nested).collect()
+}
+
+#[test]
+fn test_ignore_files() {
+    use crate::util::fs::TempRoot;
+    use std::fs::{create_dir_all, write};
+
+    let root = TempRoot::new("xbase-test-ignore-files");
+    create_dir_all(root.join("Sources/Generated")).unwrap();
+    create_dir_all(root.join("Sources/Vendor")).unwrap();
+    create_dir_all(root.join("Output")).unwrap();
+    write(
+        root.join(".gitignore"),
+        "*.log\n!keep.log\n/Output\nGenerated/\n",
+    )
+    .unwrap();
+    write(root.join("Sources/.gitignore"), "*.tmp\n").unwrap();
+    write(root.join(XBASE_IGNORE), "Sources/Vendor/\n").unwrap();
+
+    let ignore = IgnoreFiles::new(&root);
+
+    // Negation
+    assert!(ignore.is_ignored(&root.join("build.log"), false));
+    assert!(!ignore.is_ignored(&root.join("keep.log"), false));
+    // Anchoring
+    assert!(ignore.is_ignored(&root.join("Output/App"), false));
+    assert!(!ignore.is_ignored(&root.join("Sources/Output"), false));
+    // Directory only
+    assert!(ignore.is_ignored(&root.join("Sources/Generated"), true));
+    assert!(ignore.is_ignored(&root.join("Sources/Generated/A.swift"), false));
+    assert!(!ignore.is_ignored(&root.join("Generated"), false));
+    // Nested
+    assert!(ignore.is_ignored(&root.join("Sources/A.tmp"), false));
+    assert!(!ignore.is_ignored(&root.join("A.tmp"), false));
+    // .xbaseignore
+    assert!(ignore.is_ignored(&root.join("Sources/Vendor"), true));
+    assert!(!ignore.is_ignored(&root.join("Sources/A.swift"), false));
+
+    assert!(IgnoreFiles::is_ignore_file(
+        &root.join("Sources/.gitignore")
+    ));
+    assert!(IgnoreFiles::is_ignore_file(&root.join(".git/info/exclude")));
+    assert!(!IgnoreFiles::is_ignore_file(&root.join("Sources/A.swift")));
+}
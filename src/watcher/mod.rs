@@ -1,111 +1,331 @@
+mod backend;
+mod command;
 mod event;
+mod ignore_files;
 
 use crate::*;
 use async_trait::async_trait;
-use std::path::PathBuf;
+use futures::future::BoxFuture;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::{sync::Mutex, time::SystemTime};
-use tokio::sync::mpsc::{self, channel, Receiver};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, unbounded_channel, UnboundedReceiver};
 use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
 use tracing::{error, info, instrument, warn};
 
+pub use backend::*;
+pub use command::*;
 pub use event::*;
+pub use ignore_files::*;
+
+/// Quiet period after the last file change before processing changes as a batch
+const DEBOUNCE_WINDOW: Duration = Duration::from_millis(300);
+
+/// Maximum time changes are held back while files keep changing
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(2);
 
 pub struct Watcher {
     name: String,
-    state: WatcherState,
     sender: mpsc::UnboundedSender<runtime::PRMessage>,
     ignore: Vec<String>,
     abort: Arc<Notify>,
     root: PathBuf,
+    /// Directories outside project root to watch, e.g. local swift packages
+    extra_roots: Vec<PathBuf>,
+    /// Updated extra roots, e.g. once local packages are added to project manifest
+    extra_roots_updates: UnboundedReceiver<Vec<PathBuf>>,
+    config: WatcherConfig,
 }
 
+type WatcherHandle = (
+    UnboundedReceiver<notify::Event>,
+    Box<dyn notify::Watcher + Send>,
+    WatchBackend,
+);
+
 impl Watcher {
     pub fn new(
         name: &String,
-        state: &WatcherState,
         sender: &mpsc::UnboundedSender<runtime::PRMessage>,
         abort: &Arc<Notify>,
         root: &PathBuf,
         ignore: &Vec<String>,
+        extra_roots: Vec<PathBuf>,
+        extra_roots_updates: UnboundedReceiver<Vec<PathBuf>>,
+        config: &WatcherConfig,
     ) -> Self {
         Self {
             name: name.clone(),
-            state: state.clone(),
             sender: sender.clone(),
             ignore: ignore.clone(),
             abort: abort.clone(),
             root: root.clone(),
+            extra_roots,
+            extra_roots_updates,
+            config: config.clone(),
         }
     }
 
+    /// Project root followed by extra roots
+    fn roots(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.root).chain(self.extra_roots.iter())
+    }
+
     #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
-    pub async fn start(self) {
-        let (mut rx, _w) = self.get_watcher().unwrap();
+    pub async fn start(mut self) {
+        let probe_path = self.root.join(PROBE_FILE);
+        // Left behind when the daemon was killed while probing
+        if probe_path.exists() {
+            info!("Removing stale {PROBE_FILE}");
+            std::fs::remove_file(&probe_path).ok();
+        }
+
+        let (mut rx, mut _w, backend) = match self.get_watcher(self.config.backend) {
+            Ok(handle) => handle,
+            Err(err) => {
+                self.report_error(err);
+                return;
+            }
+        };
         let watchignore = self.ignore.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
-        let ignore = wax::any::<wax::Glob, _>(watchignore).unwrap();
+        let ignore = match wax::any::<wax::Glob, _>(watchignore) {
+            Ok(ignore) => ignore,
+            Err(err) => {
+                self.report_error(Error::Unexpected(format!("Invalid ignore pattern: {err}")));
+                return;
+            }
+        };
+        // Each root has its own ignore files, matched by the deepest root containing a path
+        let mut ignore_files = self
+            .roots()
+            .map(|r| IgnoreFiles::new(r))
+            .collect::<Vec<_>>();
+        let mut known_dirs = KnownDirs::new(self.roots(), |path| {
+            is_ignored_dir(&ignore, &ignore_files, path)
+        });
+        let config_path = self.root.join(CONFIG_FILE);
+        // Deadline for native events of probe file, only when backend isn't set by config
+        let mut probe = match (self.config.backend, backend) {
+            (None, WatchBackend::Native) => self.start_probe(&probe_path),
+            _ => None,
+        };
 
-        tracing::info!("Watching");
+        tracing::info!("Watching [{backend:?}] {:?}", self.extra_roots);
+        self.sender.send(PRMessage::WatchBackend(backend)).ok();
+        let mut batch = EventBatch::default();
+        let mut batch_start = Instant::now();
         loop {
+            let flush_at = (Instant::now() + DEBOUNCE_WINDOW).min(batch_start + MAX_BATCH_DELAY);
+            let probe_at = probe.unwrap_or(flush_at);
             tokio::select! {
                 _ = self.abort.notified() => break,
+                _ = tokio::time::sleep_until(probe_at), if probe.is_some() => {
+                    warn!("No native events received, falling back to polling");
+                    std::fs::remove_file(&probe_path).ok();
+                    probe = None;
+                    match self.get_watcher(Some(WatchBackend::Poll)) {
+                        Ok(handle) => {
+                            (rx, _w, _) = handle;
+                            self.sender.send(PRMessage::WatchBackend(WatchBackend::Poll)).ok();
+                        }
+                        Err(err) => self.report_error(err),
+                    }
+                }
+                Some(extra_roots) = self.extra_roots_updates.recv() => {
+                    self.update_extra_roots(
+                        extra_roots,
+                        &mut *_w,
+                        &ignore,
+                        &mut ignore_files,
+                        &mut known_dirs,
+                    );
+                }
+                _ = tokio::time::sleep_until(flush_at), if !batch.is_empty() => {
+                    tracing::debug!("Flushing {batch}");
+                    self.sender.send(PRMessage::FSEvents(std::mem::take(&mut batch))).ok();
+                }
                 event = rx.recv() => {
                     if event.is_none() { break; }
                     let event = event.unwrap();
-                    let event = match Event::new(&ignore, &self.state, event) {
+                    if probe.is_some() && event.paths.contains(&probe_path) {
+                        tracing::debug!("Native events received");
+                        std::fs::remove_file(&probe_path).ok();
+                        probe = None;
+                    }
+
+                    // Config file is hidden, so its events are ignored below
+                    if event.paths.contains(&config_path) {
+                        self.sender.send(PRMessage::ReloadConfig).ok();
+                    }
+
+                    for path in event.paths.iter().filter(|p| IgnoreFiles::is_ignore_file(p)) {
+                        if let Some(idx) = IgnoreFiles::position(&ignore_files, path) {
+                            tracing::info!("Reloading ignore files");
+                            let root = ignore_files[idx].root().to_path_buf();
+                            ignore_files[idx] = IgnoreFiles::new(&root);
+                        }
+                    }
+
+                    let position = |p: &PathBuf| IgnoreFiles::position(&ignore_files, p);
+                    let idx = event.paths.first().and_then(position).unwrap_or_default();
+                    let mut event = match Event::new(&ignore, &ignore_files[idx], event) {
                         Some(e) => e,
                         None => continue,
                     };
+                    known_dirs.update(&mut event);
 
                     // IGNORE EVENTS OF RENAME FOR PATHS THAT NO LONGER EXISTS
                     if !event.path().exists() && event.is_rename_event() {
                         tracing::debug!("{} [ignored]", event);
                         continue;
                     }
-                    self.sender.send(PRMessage::FSEvent(event)).ok();
+
+                    if batch.is_empty() {
+                        batch_start = Instant::now();
+                    }
+                    batch.push(event);
                 }
             }
         }
 
+        if probe.is_some() {
+            std::fs::remove_file(&probe_path).ok();
+        }
+
         tracing::info!("[Dropped]");
     }
 
-    fn get_watcher(&self) -> Result<(Receiver<notify::Event>, impl notify::Watcher)> {
-        use notify::{Config, RecommendedWatcher, RecursiveMode::Recursive, Watcher};
-        let (tx, rx) = channel::<notify::Event>(1);
-        let create = <RecommendedWatcher as Watcher>::new;
-        let to_err = |e: notify::Error| crate::Error::Unexpected(e.to_string());
+    /// Watch added extra roots and stop watching removed ones
+    fn update_extra_roots(
+        &mut self,
+        extra_roots: Vec<PathBuf>,
+        watcher: &mut dyn notify::Watcher,
+        ignore: &wax::Any,
+        ignore_files: &mut Vec<IgnoreFiles>,
+        known_dirs: &mut KnownDirs,
+    ) {
+        use notify::RecursiveMode::Recursive;
+        if extra_roots == self.extra_roots {
+            return;
+        }
 
-        let mut watcher = create(move |res: notify::Result<notify::Event>| {
-            res.map(|event| tx.blocking_send(event).unwrap()).ok();
-        })
-        .map_err(to_err)?;
+        for root in self.extra_roots.iter().filter(|r| !extra_roots.contains(r)) {
+            watcher.unwatch(root).ok();
+            ignore_files.retain(|files| files.root() != root.as_path());
+            known_dirs.remove_root(root);
+        }
+        for root in extra_roots.iter().filter(|r| !self.extra_roots.contains(r)) {
+            if let Err(err) = watcher.watch(root, Recursive) {
+                self.report_error(Error::Unexpected(format!("{root:?}: {err}")));
+                continue;
+            }
+            ignore_files.push(IgnoreFiles::new(root));
+            known_dirs.insert_root(root, |path| is_ignored_dir(ignore, ignore_files, path));
+        }
 
-        watcher.watch(&self.root, Recursive).map_err(to_err)?;
-        watcher
-            .configure(Config::NoticeEvents(true))
-            .map_err(to_err)?;
+        tracing::info!("Watching {extra_roots:?}");
+        self.extra_roots = extra_roots;
+    }
 
-        Ok((rx, watcher))
+    /// Report watcher failure to runtime clients
+    fn report_error(&self, err: Error) {
+        error!("Failed to watch: {err}");
+        self.sender
+            .send(PRMessage::WatchFailed(err.to_string()))
+            .ok();
     }
+
+    /// Create probe file and get deadline for its native events
+    fn start_probe(&self, path: &PathBuf) -> Option<Instant> {
+        if let Err(err) = std::fs::write(path, "") {
+            warn!("Failed to create {path:?}: {err}");
+            return None;
+        }
+        Some(Instant::now() + self.config.probe_timeout())
+    }
+
+    /// Create watcher of a given backend, native backend is preferred when no backend is given
+    fn get_watcher(&self, backend: Option<WatchBackend>) -> Result<WatcherHandle> {
+        let (tx, rx) = unbounded_channel::<notify::Event>();
+        let handler = move |res: notify::Result<notify::Event>| {
+            res.map(|event| tx.send(event).ok()).ok();
+        };
+
+        let (watcher, backend) = match backend {
+            Some(WatchBackend::Native) => (self.native_watcher(handler), WatchBackend::Native),
+            Some(WatchBackend::Poll) => (self.poll_watcher(handler), WatchBackend::Poll),
+            None => match self.native_watcher(handler.clone()) {
+                Ok(watcher) => (Ok(watcher), WatchBackend::Native),
+                Err(err) => {
+                    warn!("Failed to create native watcher, falling back to polling: {err}");
+                    (self.poll_watcher(handler), WatchBackend::Poll)
+                }
+            },
+        };
+        let watcher = watcher.map_err(|e| crate::Error::Unexpected(e.to_string()))?;
+
+        Ok((rx, watcher, backend))
+    }
+
+    fn native_watcher<F: notify::EventHandler>(
+        &self,
+        handler: F,
+    ) -> notify::Result<Box<dyn notify::Watcher + Send>> {
+        use notify::{Config, RecommendedWatcher, RecursiveMode::Recursive, Watcher};
+        let mut watcher = <RecommendedWatcher as Watcher>::new(handler)?;
+        for root in self.roots() {
+            watcher.watch(root, Recursive)?;
+        }
+        watcher.configure(Config::NoticeEvents(true))?;
+        Ok(Box::new(watcher))
+    }
+
+    fn poll_watcher<F: notify::EventHandler>(
+        &self,
+        handler: F,
+    ) -> notify::Result<Box<dyn notify::Watcher + Send>> {
+        use notify::{poll::PollWatcherConfig, PollWatcher, RecursiveMode::Recursive, Watcher};
+        let config = PollWatcherConfig {
+            poll_interval: self.config.poll_interval(),
+            compare_contents: false,
+        };
+        let mut watcher = PollWatcher::with_config(handler, config)?;
+        for root in self.roots() {
+            watcher.watch(root, Recursive)?;
+        }
+        Ok(Box::new(watcher))
+    }
+}
+
+/// Whether a directory is ignored by watchignore patterns or ignore files of its root
+fn is_ignored_dir(ignore: &wax::Any, ignore_files: &[IgnoreFiles], path: &Path) -> bool {
+    let position = IgnoreFiles::position(ignore_files, path);
+    wax::Pattern::is_match(ignore, &*path.to_string_lossy())
+        || position.map_or(false, |idx| ignore_files[idx].is_ignored(path, true))
 }
 
+/// Background work of a triggered [`Watchable`], e.g. waiting for a build to finish then running
+/// its product. Dropping the job cancels the in-flight build.
+pub type WatchJob = BoxFuture<'static, Result<()>>;
+
 /// Trait to make an object react to filesystem changes.
 #[async_trait]
 pub trait Watchable: ToString + Send + Sync + 'static {
-    /// Trigger Restart of Watchable.
+    /// Trigger Restart of Watchable, returning a job to be ran in background.
     async fn trigger(
         &self,
         project: &mut ProjectImpl,
-        ev: &Event,
+        ev: &EventBatch,
         b: &Arc<Broadcast>,
-    ) -> Result<()>;
+    ) -> Result<WatchJob>;
 
     /// A function that controls whether a a Watchable should restart
-    async fn should_trigger(&self, ev: &Event) -> bool;
+    async fn should_trigger(&self, project: &ProjectImpl, ev: &EventBatch) -> bool;
 
     /// A function that controls whether a watchable should be dropped
-    async fn should_discard(&self, ev: &Event) -> bool;
+    async fn should_discard(&self, ev: &EventBatch) -> bool;
 
     /// Drop watchable for watching a given file system
     async fn discard(&self);
@@ -115,6 +335,7 @@ pub trait Watchable: ToString + Send + Sync + 'static {
 pub struct WatchSubscribers {
     name: String,
     inner: HashMap<String, Box<(dyn Watchable + Send + Sync + 'static)>>,
+    jobs: WatchJobs,
 }
 
 impl WatchSubscribers {
@@ -122,6 +343,7 @@ impl WatchSubscribers {
         Self {
             name: name.clone(),
             inner: Default::default(),
+            jobs: Default::default(),
         }
     }
     #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
@@ -137,6 +359,7 @@ impl WatchSubscribers {
     #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
     pub async fn remove<S: ToString>(&mut self, t: &S) {
         let key = t.to_string();
+        self.jobs.abort(&key);
         if let Some(w) = self.inner.remove(&key) {
             w.discard().await;
             info!("Removed watch subscriber: `{key}`");
@@ -149,23 +372,31 @@ impl WatchSubscribers {
         self.inner.keys().map(ToString::to_string).collect()
     }
 
+    /// Run a job in background, cancelling in-flight job of the same key
+    pub fn spawn_job<S: ToString>(&mut self, key: &S, job: WatchJob, broadcast: &Arc<Broadcast>) {
+        self.jobs.spawn(key.to_string(), job, broadcast);
+    }
+
     #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
     pub async fn trigger(
         &mut self,
         project: &mut ProjectImpl,
-        event: &Event,
+        event: &EventBatch,
         broadcast: &Arc<Broadcast>,
     ) {
         let mut discards = vec![];
 
         for (key, w) in self.inner.iter() {
             if w.should_discard(&event).await {
+                self.jobs.abort(key);
                 w.discard().await;
                 discards.push(key.to_string());
-            } else if w.should_trigger(&event).await {
-                let trigger = w.trigger(project, event, broadcast);
-                if let Err(err) = trigger.await {
-                    error!("trigger errored for `{key}`!: {err}");
+            } else if w.should_trigger(project, &event).await {
+                // Cancel in-flight job before starting a new build
+                self.jobs.abort(key);
+                match w.trigger(project, event, broadcast).await {
+                    Ok(job) => self.jobs.spawn(key.clone(), job, broadcast),
+                    Err(err) => error!("trigger errored for `{key}`!: {err}"),
                 }
             }
         }
@@ -175,34 +406,57 @@ impl WatchSubscribers {
             self.inner.remove(&key);
         }
     }
-}
 
-#[derive(Clone)]
-pub struct WatcherState {
-    debounce: Arc<Mutex<SystemTime>>,
-    last_path: Arc<Mutex<PathBuf>>,
-}
-
-impl WatcherState {
-    pub fn new() -> Self {
-        Self {
-            debounce: Arc::new(Mutex::new(SystemTime::now())),
-            last_path: Default::default(),
+    /// Trigger all subscribers regardless of changes, e.g. after changes were dropped
+    #[instrument(parent = None, name = "FSWatcher", skip_all, fields(name = self.name))]
+    pub async fn trigger_all(&mut self, project: &mut ProjectImpl, broadcast: &Arc<Broadcast>) {
+        let event = EventBatch::default();
+        for (key, w) in self.inner.iter() {
+            self.jobs.abort(key);
+            match w.trigger(project, &event, broadcast).await {
+                Ok(job) => self.jobs.spawn(key.clone(), job, broadcast),
+                Err(err) => error!("trigger errored for `{key}`!: {err}"),
+            }
         }
     }
-    pub fn update_debounce(&self) {
-        let mut debounce = self.debounce.lock().unwrap();
-        *debounce = SystemTime::now();
-        tracing::trace!("Debounce updated!!!");
+}
+
+/// Running background jobs of watch subscribers by key
+#[derive(Default)]
+struct WatchJobs(HashMap<String, JoinHandle<()>>);
+
+impl WatchJobs {
+    fn spawn(&mut self, key: String, job: WatchJob, broadcast: &Arc<Broadcast>) {
+        self.abort(&key);
+        self.0.retain(|_, handle| !handle.is_finished());
+
+        let broadcast = Arc::downgrade(broadcast);
+        let handle = tokio::spawn({
+            let key = key.clone();
+            async move {
+                if let Err(err) = job.await {
+                    error!("job errored for `{key}`!: {err}");
+                    if let Some(broadcast) = broadcast.upgrade() {
+                        broadcast.error(format!("[{key}] {err}"));
+                    }
+                }
+            }
+        });
+        self.0.insert(key, handle);
     }
 
-    pub fn last_run(&self) -> u128 {
-        self.debounce.lock().unwrap().elapsed().unwrap().as_millis()
+    fn abort(&mut self, key: &str) {
+        if let Some(handle) = self.0.remove(key) {
+            if !handle.is_finished() {
+                info!("Cancelling in-flight job of `{key}`");
+                handle.abort();
+            }
+        }
     }
+}
 
-    /// Get a reference to the internal state's last path.
-    #[must_use]
-    pub fn last_path(&self) -> Arc<Mutex<PathBuf>> {
-        self.last_path.clone()
+impl Drop for WatchJobs {
+    fn drop(&mut self) {
+        self.0.values().for_each(JoinHandle::abort);
     }
 }
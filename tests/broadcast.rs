@@ -0,0 +1,200 @@
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UnixStream;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tokio::time::timeout;
+use tokio_util::codec::{Framed, LinesCodec};
+use xbase::server::{add_session, set_session_capabilities, PROTOCOL_VERSION};
+use xbase::{Broadcast, ContentLevel, Message, Notification, ReplayFilter, State};
+use xbase::{Task, TaskKind};
+
+async fn next_notification(rx: &mut UnboundedReceiver<String>) -> Notification {
+    let line = timeout(Duration::from_secs(1), rx.recv())
+        .await
+        .expect("notification within a second")
+        .expect("open session");
+    serde_json::from_str(&line).unwrap()
+}
+
+/// Minimal barebone project, setup only needs to read its xcodeproj
+fn write_barebone_project(root: &Path) {
+    std::fs::remove_dir_all(root).ok();
+    std::fs::create_dir_all(root.join("Demo.xcodeproj")).unwrap();
+    std::fs::write(
+        root.join("Demo.xcodeproj/project.pbxproj"),
+        r#"// !$*UTF8*$!
+{
+	archiveVersion = 1;
+	classes = {
+	};
+	objectVersion = 55;
+	objects = {
+		G1 = {
+			isa = PBXGroup;
+			children = (
+			);
+			sourceTree = "<group>";
+		};
+		C1 /* Debug */ = {
+			isa = XCBuildConfiguration;
+			buildSettings = {
+			};
+			name = Debug;
+		};
+		L1 /* Build configuration list for PBXProject "Demo" */ = {
+			isa = XCConfigurationList;
+			buildConfigurations = (
+				C1 /* Debug */,
+			);
+			defaultConfigurationIsVisible = 0;
+			defaultConfigurationName = Debug;
+		};
+		P1 /* Project object */ = {
+			isa = PBXProject;
+			buildConfigurationList = L1 /* Build configuration list for PBXProject "Demo" */;
+			compatibilityVersion = "Xcode 13.0";
+			mainGroup = G1;
+			projectDirPath = "";
+			projectRoot = "";
+			targets = (
+			);
+		};
+	};
+	rootObject = P1 /* Project object */;
+}
+"#,
+    )
+    .unwrap();
+}
+
+async fn next_line(client: &mut Framed<UnixStream, LinesCodec>) -> Value {
+    let line = timeout(Duration::from_secs(5), client.next())
+        .await
+        .expect("line within five seconds")
+        .expect("open connection")
+        .unwrap();
+    serde_json::from_str(&line).unwrap()
+}
+
+#[tokio::test]
+async fn test_initial_state_queued_till_attached() {
+    let root = std::env::temp_dir()
+        .join("xbase-test-register")
+        .join("Demo");
+    write_barebone_project(&root);
+
+    let (client, daemon) = UnixStream::pair().unwrap();
+    let connection = tokio::spawn(xbase::server::handle(daemon));
+    let mut client = Framed::new(client, LinesCodec::new());
+    let hello = json!({
+        "id": 1,
+        "method": "hello",
+        "args": { "client_name": "test", "protocol_version": PROTOCOL_VERSION }
+    });
+    let register = json!({ "id": 2, "method": "register", "args": { "root": root } });
+    client.send(hello.to_string()).await.unwrap();
+    assert_eq!(next_line(&mut client).await["id"], 1);
+    client.send(register.to_string()).await.unwrap();
+
+    let response = next_line(&mut client).await;
+    assert_eq!(response["id"], 2, "register response written first");
+    assert!(response.get("error").is_none(), "{response}");
+
+    let mut notifications = vec![];
+    while !notifications.iter().any(|notification: &Notification| {
+        matches!(
+            notification.message,
+            Message::SetState(State::ProjectInfo(_))
+        )
+    }) {
+        let line = next_line(&mut client).await;
+        assert!(line.get("message").is_some(), "unexpected line {line}");
+        notifications.push(serde_json::from_value(line).unwrap());
+    }
+    assert_eq!(notifications[0].root, root);
+    assert!(matches!(
+        &notifications[0].message,
+        Message::Notify { content, .. } if content == "[Demo] Registered"
+    ));
+
+    drop(client);
+    connection.await.unwrap();
+    std::fs::remove_dir_all(root.parent().unwrap()).ok();
+}
+
+#[tokio::test]
+async fn test_history_replayed_to_late_subscriber() {
+    let (tx, mut rx) = unbounded_channel();
+    let session = add_session(tx);
+    let broadcast = Broadcast::new("/tmp/xbase/Demo");
+
+    broadcast.log_info("[Demo] Compiling");
+    broadcast.info("[Demo] Not kept in history");
+    broadcast.log_error("[Demo] Compile failed");
+
+    let replay = ReplayFilter {
+        level: Some(ContentLevel::Warn),
+        task: None,
+    };
+    broadcast.subscribe(session, Some(replay));
+    broadcast.attach(session);
+    broadcast.info("[Demo] Registered");
+
+    let notification = next_notification(&mut rx).await;
+    assert!(
+        matches!(notification.message, Message::Log { content, .. } if content == "[Demo] Compile failed")
+    );
+    let notification = next_notification(&mut rx).await;
+    assert!(
+        matches!(notification.message, Message::Notify { content, .. } if content == "[Demo] Registered")
+    );
+}
+
+#[tokio::test]
+async fn test_gated_task_messages_skipped() {
+    let (tx, mut rx) = unbounded_channel();
+    let session = add_session(tx);
+    let (tx, mut capable_rx) = unbounded_channel();
+    let capable_session = add_session(tx);
+    set_session_capabilities(capable_session, &["task_kind_command".into()]);
+    let broadcast = Arc::new(Broadcast::new("/tmp/xbase/Demo"));
+
+    for session in [session, capable_session] {
+        broadcast.subscribe(session, None);
+        broadcast.attach(session);
+    }
+    Task::new(TaskKind::Command, "lint", broadcast.clone()).info("[lint] Linting");
+    let build = Task::new(TaskKind::Build, "Demo", broadcast.clone());
+    build.info("[Demo] Building");
+
+    let notification = next_notification(&mut rx).await;
+    assert_eq!(notification.task, Some(build.id()));
+    assert!(matches!(
+        notification.message,
+        Message::SetCurrentTask {
+            kind: TaskKind::Build,
+            ..
+        }
+    ));
+    let notification = next_notification(&mut rx).await;
+    assert_eq!(notification.task, Some(build.id()));
+    assert!(
+        matches!(notification.message, Message::UpdateCurrentTask { content, .. } if content == "[Demo] Building")
+    );
+
+    let notification = next_notification(&mut capable_rx).await;
+    assert!(matches!(
+        notification.message,
+        Message::SetCurrentTask {
+            kind: TaskKind::Command,
+            ..
+        }
+    ));
+    let notification = next_notification(&mut capable_rx).await;
+    assert!(
+        matches!(notification.message, Message::UpdateCurrentTask { content, .. } if content == "[lint] Linting")
+    );
+}
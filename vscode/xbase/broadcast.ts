@@ -1,5 +1,3 @@
-import { filter, map, pipe, split, toAsync } from "iter-ops";
-import net from "net";
 import { Disposable, window, commands } from "vscode";
 import { Message, ContentLevel, TaskKind, TaskStatus } from "./types";
 import Logger from "./ui/logger";
@@ -19,52 +17,29 @@ interface CurrentTask {
 export default class Broadcast implements Disposable {
   public name: string;
   public folderCtx: FolderContext;
-  public socket: net.Socket;
+  private ctx: WorkspaceContext;
   private logger: Logger;
   private statusline: Statusline;
   private currentTask?: CurrentTask;
   private sourcekit: SourcekitLsp;
 
-  private constructor(
-    folder: FolderContext, socket: net.Socket, ctx: WorkspaceContext
-  ) {
+  private constructor(folder: FolderContext, ctx: WorkspaceContext) {
     this.folderCtx = folder;
     this.name = folder.name.charAt(0).toUpperCase() + folder.name.slice(1);
-    this.socket = socket;
+    this.ctx = ctx;
     this.logger = ctx.logger;
     this.statusline = ctx.statusline;
     this.sourcekit = ctx.sourcekit;
   }
 
-  public static async connect(
-    folder: FolderContext,
-    address: string,
-    ctx: WorkspaceContext
-  ): Promise<Broadcast> {
-    return new Promise((resolve, reject) => {
-      const socket = net.createConnection(address, () => {
-        const broadcast = new Broadcast(folder, socket, ctx);
-        socket.on("data", async buffer => {
-          for await (const message of Broadcast.get_messages(buffer))
-            await broadcast.handleMessage(message);
-        });
-        socket.write(`${process.pid}\n`);
-        resolve(broadcast);
-      });
-      socket.on("error", err => {
-        reject(Error(`Failed to connect to XBase Broadcast: ${err}`));
-      });
-    });
-  }
-
-  private static get_messages(buffer: Buffer): AsyncIterable<Message> {
-    return pipe(
-      toAsync(buffer),
-      split(a => a === 10),
-      map(m => Buffer.from(m)),
-      filter(m => m.length > 1),
-      map(m => JSON.parse(m.toString()) as Message)
-    );
+  /**
+    * Handle messages pushed by the server for the folder root.
+    * Must be called before registering the root so no message is missed.
+  */
+  public static subscribe(folder: FolderContext, ctx: WorkspaceContext): Broadcast {
+    const broadcast = new Broadcast(folder, ctx);
+    ctx.server.subscribe(folder.uri.fsPath, message => broadcast.handleMessage(message));
+    return broadcast;
   }
 
   private async handleMessage(message: Message) {
@@ -197,9 +172,7 @@ export default class Broadcast implements Disposable {
   }
 
   dispose() {
-    this.socket.pause();
-    this.socket.end();
-    this.socket.destroy();
+    this.ctx.server.unsubscribe(this.folderCtx.uri.fsPath);
   }
 }
 
@@ -2,5 +2,11 @@
 //
 // ----
 // AUTOGENERATED
-export const XBASE_SOCK_ADDR = '/tmp/xbase.socket'
+export const XBASE_RUNTIME_DIR =
+  process.env.XBASE_RUNTIME_DIR ||
+  (process.env.XDG_RUNTIME_DIR
+    ? `${process.env.XDG_RUNTIME_DIR}/xbase`
+    : `${(process.env.TMPDIR || '/tmp').replace(/\/$/, '')}/xbase-${process.getuid!()}`)
+export const XBASE_SOCK_ADDR = process.env.XBASE_SOCK_ADDR || `${XBASE_RUNTIME_DIR}/xbase.socket`
+export const XBASE_PROTOCOL_VERSION = 2
 export const XBASE_BIN_ROOT = '$HOME/.local/share/xbase'.replace('$HOME', process.env.HOME!)
@@ -6,7 +6,7 @@ import { projectName } from "./util";
 import { WorkspaceContext } from "./workspaceContext";
 
 export default class FolderContext implements Disposable {
-  public projectInfo: ProjectInfo = { watchlist: [], targets: {} };
+  public projectInfo: ProjectInfo = { watchlist: [], targets: {}, watcher: null };
   public subscriptions: Disposable[] = [];
   private constructor(
     public ctx: WorkspaceContext,
@@ -28,12 +28,14 @@ export default class FolderContext implements Disposable {
     ctx.statusline.update({ content: registering });
     console.log(registering);
 
-    folderCtx.subscriptions.push(
-      await ctx.server.register(uri.fsPath)
-        .then(address => Broadcast.connect(folderCtx, address, ctx))
-        .catch(error => {
-          throw Error(`[${name}] Failed to Initialize: ${error}`);
-        }));
+    const broadcast = Broadcast.subscribe(folderCtx, ctx);
+    folderCtx.subscriptions.push(broadcast);
+
+    await ctx.server.register(uri.fsPath)
+      .catch(error => {
+        broadcast.dispose();
+        throw Error(`[${name}] Failed to Initialize: ${error}`);
+      });
 
     ctx.statusline.setDefault();
     console.log(`[${name}] Registered`);
@@ -1,23 +1,73 @@
 import net from "net";
-import type { JSONValue, Request, Response } from "./types";
+import type { JSONValue, Message, Notification, Request, RequestMessage, Response } from "./types";
 import { Disposable } from "vscode";
 import { spawn } from "child_process";
-import { XBASE_BIN_ROOT } from "./constants";
+import { XBASE_BIN_ROOT, XBASE_PROTOCOL_VERSION, XBASE_SOCK_ADDR } from "./constants";
 
 export default class Server implements Disposable {
   roots: string[] = [];
 
-  private static onConnect = (resolve: (value: Server) => void, socket: net.Socket) =>
+  private static onConnect = (
+    resolve: (value: Server) => void, reject: (reason: Error) => void, socket: net.Socket
+  ) =>
     () => {
       console.log("[XBase] Server Connected");
-      resolve(new Server(socket));
+      const server = new Server(socket);
+      server
+        .request({
+          method: "hello",
+          args: { client_name: "vscode", protocol_version: XBASE_PROTOCOL_VERSION, capabilities: ["task_kind_resolve", "task_kind_command"] }
+        })
+        .then(() => resolve(server))
+        .catch(reject);
     };
   ;
-  private constructor(public socket: net.Socket) { }
+  private lastId = 0;
+  private buffer = "";
+  private pending = new Map<number, (response: Response) => void>();
+  private subscribers = new Map<string, (message: Message) => Promise<void>>();
+
+  private constructor(public socket: net.Socket) {
+    socket.on("data", (chunk) => this.onData(chunk));
+  }
+
+  /**
+    * Buffer new line delimited json objects, resolve pending requests matching response ids
+    * and pass project messages to root subscribers
+  */
+  private onData(chunk: Buffer) {
+    this.buffer += chunk.toString();
+    const lines = this.buffer.split("\n");
+    this.buffer = lines.pop() ?? "";
+
+    for (const line of lines) {
+      if (line.trim().length === 0) continue;
+      const item = JSON.parse(line) as Response | Notification;
+      if ("root" in item) {
+        this.subscribers.get(item.root)?.(item.message)
+          .catch(error => console.error(`[XBase] Failed to handle message: ${error}`));
+      } else {
+        const onResponse = this.pending.get(item.id);
+        this.pending.delete(item.id);
+        onResponse?.(item);
+      }
+    }
+  }
+
+  /**
+    * Handle messages pushed for a given root
+  */
+  subscribe(root: string, handler: (message: Message) => Promise<void>) {
+    this.subscribers.set(root, handler);
+  }
+
+  unsubscribe(root: string) {
+    this.subscribers.delete(root);
+  }
 
   public static async connect(): Promise<Server> {
     return new Promise((resolve, reject) => {
-      const socket = net.createConnection("/tmp/xbase.socket");
+      const socket = net.createConnection(XBASE_SOCK_ADDR);
 
       socket.on("error", () => {
         console.log("[XBase] No socket running, spawning");
@@ -27,31 +77,28 @@ export default class Server implements Disposable {
         // The timeout is needed to give some time for xbase to startup
         // NOTE: child.on('spawn') doesn't cut it.
         setTimeout(() => {
-          const socket = net.createConnection("/tmp/xbase.socket");
-          socket.on("connect", Server.onConnect(resolve, socket));
+          const socket = net.createConnection(XBASE_SOCK_ADDR);
+          socket.on("connect", Server.onConnect(resolve, reject, socket));
           socket.on("error", (err) => reject(Error(`Failed to connect to xbase socket: ${err}`)));
         }, 500);
       });
 
-      socket.on("connect", Server.onConnect(resolve, socket));
+      socket.on("connect", Server.onConnect(resolve, reject, socket));
     });
   }
 
-  // Register a given root
-  async register(root: string): Promise<string> {
-    const value = await this.request({ method: "register", args: { root, id: process.pid } })
+  // Register a given root, its messages are passed to root subscriber
+  async register(root: string): Promise<void> {
+    // The daemon replays recent logs by default, so the logger shows tasks started by other clients
+    await this.request({ method: "register", args: { root } })
       .catch(error => {
         throw Error(`Registeration failed: ${error}`);
       });
-
-    if (typeof value === "string") return value;
-
-    throw Error(`Expected response to be a string, got ${value}`);
   }
 
   // Drop a root project
   async drop(root: string): Promise<void> {
-    await this.request({ method: "drop", args: { id: process.pid, roots: [root] } })
+    await this.request({ method: "drop", args: { roots: [root] } })
       .catch(error => {
         throw Error(`Drop failed: ${error}`);
       });
@@ -62,20 +109,21 @@ export default class Server implements Disposable {
   */
   public async request(req: Request): Promise<JSONValue | undefined> {
     const { socket } = this;
-    const data = JSON.stringify(req);
+    const id = ++this.lastId;
+    const data = JSON.stringify({ ...req, id } as RequestMessage);
 
     return new Promise((resolve, reject) => {
+      this.pending.set(id, ({ error, data }) => {
+        if (error)
+          reject(new Error(`Server Errored: (${error.kind}): ${error.msg}`));
+        else
+          resolve(data);
+      });
+
       socket.write(`${data}\n`, (error) => {
         if (error !== undefined) {
+          this.pending.delete(id);
           return reject(new Error);
-        } else {
-          socket.once("data", (buffer) => {
-            const { error, data } = JSON.parse(`${buffer}`) as Response;
-            if (error)
-              reject(new Error(`Server Errored: (${error.kind}): ${error.msg}`));
-            else
-              resolve(data);
-          });
         }
       });
     });
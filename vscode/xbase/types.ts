@@ -13,12 +13,18 @@ export namespace TaskKind {
         done: "Generated",
         value: "Generate",
       };
+    } else if (isResolve(kind)) {
+      return { processing: "Resolving", done: "Resolved", value: "Resolve" };
+    } else if (isCommand(kind)) {
+      return { processing: "Running", done: "Ran", value: "Command" };
     }
   };
   export const isRun = (kind: TaskKind) => kind === "Run";
   export const isBuild = (kind: TaskKind) => kind === "Build";
   export const isGenerate = (kind: TaskKind) => kind === "Generate";
   export const isCompile = (kind: TaskKind) => kind === "Compile";
+  export const isResolve = (kind: TaskKind) => kind === "Resolve";
+  export const isCommand = (kind: TaskKind) => kind === "Command";
 }
 
 // AUTOGENERATED
@@ -67,7 +73,15 @@ export type TaskKind =
   /**
    * Generate xcodeproj
    */
-  | "Generate";
+  | "Generate"
+  /**
+   * Resolve project dependencies, e.g. `tuist install` or `swift package resolve`
+   */
+  | "Resolve"
+  /**
+   * User defined command, see [`crate::CommandConfig`]
+   */
+  | "Command";
 
 /**
  * What the status of task is currently under progress?
@@ -130,6 +144,31 @@ export type TargetInfo =
    * Target specfic information
    */
   { platform: string; configurations: string[] };
+
+/**
+ * File watcher backend
+ */
+export type WatchBackend =
+  /**
+   * Platform native events, e.g. inotify or fsevents
+   */
+  | "native"
+  /**
+   * Periodic scan of project files, works on network and container filesystems
+   */
+  | "poll";
+/**
+ * How file changes received while watching is paused are handled
+ */
+export type PauseMode =
+  /**
+   * Buffer changes and process them at once when watching is resumed
+   */
+  | "buffer"
+  /**
+   * Drop changes
+   */
+  | "drop";
 export type ProjectInfo = {
   /**
    * Get watched configurations for given root
@@ -139,6 +178,18 @@ export type ProjectInfo = {
    * Get targets information for a registers project with a given root
    */
   targets: Record<string, TargetInfo>;
+  /**
+   * Active file watcher backend, None till the watcher is started
+   */
+  watcher: WatchBackend | null;
+  /**
+   * Names of commands configured in project `.xbase.json`
+   */
+  commands: string[];
+  /**
+   * How file changes are handled while watching is paused, None if not paused
+   */
+  paused: PauseMode | null;
 };
 
 /**
@@ -230,17 +281,50 @@ export type Response =
   /**
    * Server Response
    */
-  { data?: JSONValue; error?: ServerError };
+  {
+    /**
+     * Id of the request this response is for
+     */
+    id: U32;
+    data?: JSONValue;
+    error?: ServerError;
+  };
 export type U32 = number;
 
 /**
  * Register a project root
  */
+export type Usize = number;
+
+/**
+ * Filter of project messages replayed to a newly registered client
+ */
+export type ReplayFilter = {
+  /**
+   * Only replay messages with at least the given level
+   */
+  level?: ContentLevel | null;
+  /**
+   * Only replay messages of a given task, see [`TaskInfo`]
+   */
+  task?: Usize | null;
+};
+
 export type RegisterRequest =
   /**
    * Register a project root
    */
-  { id: U32; root: string };
+  {
+    root: string;
+    /**
+     * Replay recent project logs and tasks matching a given filter
+     */
+    replay?: ReplayFilter;
+    /**
+     * Skip replaying recent project logs and tasks
+     */
+    no_replay?: boolean;
+  };
 
 /**
  * Request to build a particular project
@@ -265,6 +349,22 @@ export type RunRequest =
     operation: Operation;
   };
 
+/**
+ * Request to run or watch a command configured in project `.xbase.json`
+ */
+export type CommandRequest =
+  /**
+   * Request to run or watch a command configured in project `.xbase.json`
+   */
+  {
+    root: string;
+    /**
+     * Name of the command, see [`CommandConfig`]
+     */
+    name: string;
+    operation: Operation;
+  };
+
 /**
  * Drop a given set of roots to be dropped (i.e. unregistered)
  */
@@ -272,7 +372,40 @@ export type DropRequest =
   /**
    * Drop a given set of roots to be dropped (i.e. unregistered)
    */
-  { id: U32; roots: string[] };
+  { roots: string[] };
+
+/**
+ * Request to list past build and run logs of a project root
+ */
+export type LogsRequest =
+  /**
+   * Request to list past build and run logs of a project root
+   */
+  { root: string };
+
+/**
+ * Request to get content of a past log by name, see [`LogsRequest`]
+ */
+export type ReadLogRequest =
+  /**
+   * Request to get content of a past log by name, see [`LogsRequest`]
+   */
+  { root: string; name: string };
+
+/**
+ * Request to get recorded builds of a project root with per target averages and trends
+ */
+export type BuildHistoryRequest =
+  /**
+   * Request to get recorded builds of a project root with per target averages and trends
+   */
+  {
+    root: string;
+    /**
+     * Only get builds of a given target
+     */
+    target?: string | null;
+  };
 
 /**
  * Requests clinets can make
@@ -280,7 +413,14 @@ export type DropRequest =
 export type Request =
   | {
       /**
-       * Register project root and get broadcaster reader file description
+       * Negotiate protocol version and capabilities, must be sent first on a new connection
+       */
+      method: "hello";
+      args: HelloRequest;
+    }
+  | {
+      /**
+       * Register project root and receive its messages as notifications on the same connection
        */
       method: "register";
       args: RegisterRequest;
@@ -299,10 +439,249 @@ export type Request =
       method: "run";
       args: RunRequest;
     }
+  | {
+      /**
+       * Run or watch a project command and get path to where its log will be located
+       */
+      method: "command";
+      args: CommandRequest;
+    }
   | {
       /**
        * Drop projects at a given roots
        */
       method: "drop";
       args: DropRequest;
+    }
+  | {
+      /**
+       * List past build and run logs of a project, newest first
+       */
+      method: "logs";
+      args: LogsRequest;
+    }
+  | {
+      /**
+       * Get content of a past build or run log
+       */
+      method: "read_log";
+      args: ReadLogRequest;
+    }
+  | {
+      /**
+       * Get recorded builds of a project with per target averages and trends
+       */
+      method: "build_history";
+      args: BuildHistoryRequest;
+    }
+  | {
+      /**
+       * Pause processing file changes of a project, e.g. during a rebase
+       */
+      method: "pause_watch";
+      args: PauseWatchRequest;
+    }
+  | {
+      /**
+       * Resume processing file changes of a project, buffered changes are processed at once
+       */
+      method: "resume_watch";
+      args: ResumeWatchRequest;
     };
+
+/**
+ * Request to pause processing file changes of a project
+ */
+export type PauseWatchRequest =
+  /**
+   * Request to pause processing file changes of a project
+   */
+  {
+    root: string;
+    /**
+     * Whether changes are buffered till watching is resumed or dropped, default buffer
+     */
+    mode?: PauseMode;
+  };
+
+/**
+ * Request to resume processing file changes of a paused project
+ */
+export type ResumeWatchRequest =
+  /**
+   * Request to resume processing file changes of a paused project
+   */
+  { root: string };
+
+export type U64 = number;
+
+/**
+ * Past task log file
+ */
+export type TaskLogInfo =
+  /**
+   * Past task log file
+   */
+  {
+    /**
+     * Log file name, e.g. `Build_App.log` for latest log and `Build_App.1.log` for the one before
+     */
+    name: string;
+    /**
+     * Whether the log holds unfiltered process output, e.g. `Build_App.raw.log`
+     */
+    raw: boolean;
+    path: string;
+    /**
+     * Size in bytes
+     */
+    size: U64;
+    /**
+     * Last modification time in seconds since unix epoch
+     */
+    modified: U64;
+  };
+
+/**
+ * Request along with an id chosen by the client to match it with its response
+ */
+export type RequestMessage =
+  /**
+   * Request along with an id chosen by the client to match it with its response
+   */
+  {
+    /**
+     * Request id, echoed back in the request response
+     */
+    id?: U32;
+  } & Request;
+
+/**
+ * Introduce client to the daemon, must be sent first on a new connection
+ */
+export type HelloRequest =
+  /**
+   * Introduce client to the daemon, must be sent first on a new connection
+   */
+  {
+    /**
+     * Client name (e.g. nvim, vscode)
+     */
+    client_name: string;
+    /**
+     * Protocol version the client bindings were generated with
+     */
+    protocol_version: U32;
+    /**
+     * Optional message kinds the client can process, see [`Message::capability`]
+     */
+    capabilities?: string[];
+  };
+
+/**
+ * Daemon version along with supported requests and messages
+ */
+export type HelloResponse =
+  /**
+   * Daemon version along with supported requests and messages
+   */
+  {
+    version: string;
+    protocol_version: U32;
+    requests: string[];
+    messages: string[];
+    /**
+     * Capabilities clients can declare, see [`Message::capability`]
+     */
+    capabilities: string[];
+  };
+
+/**
+ * Message pushed on client connection for a registered project root
+ */
+export type Notification =
+  /**
+   * Message pushed on client connection for a registered project root
+   */
+  {
+    root: string;
+    message: Message;
+    /**
+     * Id of the task the message belongs to, see [`TaskInfo`]
+     */
+    task?: Usize;
+  };
+
+export type F64 = number;
+
+/**
+ * Finished build of a project target
+ */
+export type BuildRecord =
+  /**
+   * Finished build of a project target
+   */
+  {
+    target: string;
+    configuration: string;
+    /**
+     * Name of the device the build was made for
+     */
+    device: string | null;
+    /**
+     * Start time in seconds since unix epoch
+     */
+    started_at: U64;
+    /**
+     * Duration in milliseconds
+     */
+    duration: U64;
+    success: boolean;
+    warnings: Usize;
+    errors: Usize;
+    /**
+     * First changed file that triggered the build in watch mode
+     */
+    trigger: string | null;
+  };
+
+/**
+ * Build statistics of a project target
+ */
+export type BuildStats =
+  /**
+   * Build statistics of a project target
+   */
+  {
+    target: string;
+    builds: Usize;
+    failures: Usize;
+    /**
+     * Average duration of successful builds in milliseconds
+     */
+    average_duration: U64;
+    /**
+     * Average warnings count of successful builds
+     */
+    average_warnings: F64;
+    /**
+     * Duration change in percent of the last successful builds compared to the ones before,
+     * None till there are enough builds to compare
+     */
+    trend: F64 | null;
+  };
+
+/**
+ * Recorded builds of a project along with per target statistics
+ */
+export type BuildHistory =
+  /**
+   * Recorded builds of a project along with per target statistics
+   */
+  {
+    /**
+     * Build records, oldest first
+     */
+    records: BuildRecord[];
+    stats: BuildStats[];
+  };